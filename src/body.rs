@@ -0,0 +1,76 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use bevy::prelude::*;
+
+use crate::config::SectariiConfig;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BodyConfig {
+    /// chance of each gene to mutate in an offspring
+    pub mutation_rate: f32,
+    /// largest relative change of a mutated gene
+    pub mutation_scale: f32,
+    /// largest change of the hue in degrees
+    pub hue_drift: f32,
+    /// largest hue difference in degrees between a new species and its parent species
+    pub speciation_drift: f32,
+    pub size: (f32, f32),
+    pub density: (f32, f32),
+    pub sight_range: (f32, f32),
+    pub sight_fov: (f32, f32),
+    pub steal_radius: (f32, f32),
+    /// satiety decay is scaled by `size^size_cost`
+    pub size_cost: f32,
+}
+impl Default for BodyConfig {
+    fn default() -> Self {
+        Self {
+            mutation_rate: 0.2, mutation_scale: 0.1, hue_drift: 4., speciation_drift: 30.,
+            size: (0.5, 2.5), density: (0.3, 3.), sight_range: (40., 500.), sight_fov: (0.2, 6.), steal_radius: (1., 8.),
+            size_cost: 2.,
+        }
+    }
+}
+
+/// Heritable body plan, passed to offspring with small mutations next to the network.
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Body {
+    /// scale of the whole body, collider and mesh included
+    pub size: f32,
+    /// multiplier of the collider density
+    pub density: f32,
+    pub sight_range: f32,
+    pub sight_fov: f32,
+    pub steal_radius: f32,
+    /// degrees
+    pub hue: f32,
+}
+
+impl Body {
+    pub fn new(c: &SectariiConfig, hue: f32) -> Self {
+        Self { size: 1., density: 1., sight_range: c.sight_range, sight_fov: c.sight_fov, steal_radius: 2.5, hue }
+    }
+
+    pub fn mutate(&self, c: &BodyConfig, rng: &mut impl Rng) -> Self {
+        let mut gene = |v: f32, bounds: (f32, f32)| {
+            if !rng.random_bool(c.mutation_rate.clamp(0., 1.) as f64) { return v }
+            (v * (1. + rng.random_range(-1_f32..=1.) * c.mutation_scale)).clamp(bounds.0, bounds.1)
+        };
+        let mut body = Self {
+            size: gene(self.size, c.size),
+            density: gene(self.density, c.density),
+            sight_range: gene(self.sight_range, c.sight_range),
+            sight_fov: gene(self.sight_fov, c.sight_fov),
+            steal_radius: gene(self.steal_radius, c.steal_radius),
+            hue: self.hue,
+        };
+        body.hue = (body.hue + rng.random_range(-1_f32..=1.) * c.hue_drift).rem_euclid(360.);
+        body
+    }
+
+    /// Multiplier of the satiety decay.
+    pub fn metabolism(&self, c: &BodyConfig) -> f32 {
+        self.size.powf(c.size_cost)
+    }
+}
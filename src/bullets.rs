@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use avian2d::prelude::*;
+
+use crate::{config::SimConfig, death::{Damage, DeathCause}, events::AttackEvent, sectarii::{Brain, Sectarian, Species}, GameLayer, Hp, SimSet, Stamina};
+
+pub struct BulletsPlugin;
+
+impl Plugin for BulletsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(FixedUpdate, (fire_system, bullet_system).chain().in_set(SimSet::Attacks))
+        ;
+    }
+}
+
+#[derive(Component, Clone)]
+pub struct Bullet {
+    pub shooter: Entity,
+    pub key: usize,
+    pub species: usize,
+    pub lifetime: f32,
+}
+
+/// Seconds left until the sectarian can fire again.
+#[derive(Component, Clone, Default)]
+pub struct Gun(pub f32);
+
+fn fire_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    mut sectarii: Query<(Entity, &Brain, &Species, &mut Gun, &mut Stamina, &Transform, &LinearVelocity), With<Sectarian>>,
+){
+    let c = &config.bullets;
+    sectarii.iter_mut().for_each(|(e, brain, species, mut gun, mut stamina, transform, lv)| {
+        gun.0 -= time.delta_secs();
+        if gun.0 > 0. || brain.fire < c.threshold || stamina.0 < c.cost { return }
+        gun.0 = c.cooldown;
+        stamina.0 -= c.cost;
+
+        let dir = transform.local_y().truncate().normalize_or_zero();
+        commands.spawn((
+            Bullet { shooter: e, key: brain.key, species: species.0, lifetime: c.lifetime },
+            Transform::from_translation(transform.translation + (dir * 12.).extend(0.)),
+            RigidBody::Kinematic,
+            Collider::circle(1.5),
+            Sensor,
+            CollisionLayers::new([GameLayer::Bullets], [GameLayer::Sectarii, GameLayer::Foods, GameLayer::Walls, GameLayer::Obstacles]),
+            CollidingEntities::default(),
+            LinearVelocity(lv.0 + dir * c.speed),
+        ));
+    } );
+}
+
+fn bullet_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    mut bullets: Query<(Entity, &mut Bullet, &CollidingEntities)>,
+    mut sectarii: Query<(&mut Hp, &Brain, &Species, &mut Damage), With<Sectarian>>,
+    mut attacks: EventWriter<AttackEvent>,
+){
+    let damage = config.bullets.damage;
+    let kin = config.sectarii.same_species_predation;
+    bullets.iter_mut().for_each(|(e, mut bullet, ce)| {
+        bullet.lifetime -= time.delta_secs();
+        // anything but the shooter stops the bullet: sectarii, food, walls or obstacles
+        let target = ce.iter().find(|t| **t != bullet.shooter );
+
+        if let Some(t) = target {
+            if let Some(mut enemy) = sectarii.get_mut(*t).ok().filter(|s| kin || s.2.0 != bullet.species) {
+                enemy.0.0 -= damage;
+                enemy.3.attack(DeathCause::Shot, bullet.shooter, bullet.key, damage);
+                attacks.send(AttackEvent {
+                    attacker: bullet.shooter, attacker_key: bullet.key, target: *t, target_key: enemy.1.key,
+                    cause: DeathCause::Shot, hp: damage,
+                });
+            }
+            commands.entity(e).despawn();
+        } else if bullet.lifetime <= 0. {
+            commands.entity(e).despawn();
+        }
+    } );
+}
@@ -0,0 +1,361 @@
+use std::{fs, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use avian2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{body::BodyConfig, environment::EnvironmentConfig, fertility::FertilityConfig, fitness::FitnessConfig, food::FoodKind, map::MapConfig, terrain::TerrainConfig, world::WorldConfig};
+
+pub struct ConfigPlugin {
+    pub path: Option<PathBuf>,
+    pub overrides: Vec<String>,
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let config = match &self.path {
+            Some(p) => SimConfig::load(p, &self.overrides).unwrap_or_else(|e| panic!("config {:?}: {}", p, e)),
+            None => SimConfig::from_table(toml::Table::new(), &self.overrides).unwrap_or_else(|e| panic!("config overrides: {}", e)),
+        };
+
+        app
+            .insert_resource(config)
+            .insert_resource(ConfigWatcher {
+                path: self.path.clone(),
+                overrides: self.overrides.clone(),
+                modified: self.path.as_ref().and_then(|p| modified(p)),
+            })
+            .add_systems(Update, hot_reload.run_if(on_timer(Duration::from_secs(1))))
+            .add_systems(Update, apply_physics.run_if(resource_changed::<SimConfig>))
+        ;
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SimConfig {
+    /// random seed for the whole run, picked at random when missing
+    pub seed: Option<u64>,
+    pub physics: PhysicsConfig,
+    pub world: WorldConfig,
+    pub map: MapConfig,
+    pub terrain: TerrainConfig,
+    pub fertility: FertilityConfig,
+    pub environment: EnvironmentConfig,
+    pub metabolism: MetabolismConfig,
+    pub food: FoodConfig,
+    pub sectarii: SectariiConfig,
+    pub body: BodyConfig,
+    pub fitness: FitnessConfig,
+    pub bullets: BulletsConfig,
+    pub snapshot: SnapshotConfig,
+    pub stats: StatsConfig,
+    pub lineage: LineageConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PhysicsConfig {
+    pub fixed_hz: f64,
+    pub relative_speed: f32,
+    pub substeps: u32,
+}
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self { fixed_hz: 48., relative_speed: 4., substeps: 4 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MetabolismConfig {
+    /// seconds for a full unit of satiety to run out
+    pub satiety_decay: f32,
+    /// seconds for a full unit of hp to run out while starving
+    pub starvation: f32,
+    /// linear velocity below which stamina regenerates
+    pub stamina_free_speed: f32,
+    pub stamina_regen: f32,
+    /// stamina cost is `(linvel - stamina_cost_offset)^2 * stamina_cost` per second
+    pub stamina_cost_offset: f32,
+    pub stamina_cost: f32,
+}
+impl Default for MetabolismConfig {
+    fn default() -> Self {
+        Self { satiety_decay: 45., starvation: 15., stamina_free_speed: 10., stamina_regen: 1., stamina_cost_offset: 9., stamina_cost: 0.00075 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FoodConfig {
+    pub initial_count: usize,
+    pub initial_range: f32,
+    pub initial_hp: (f32, f32),
+    pub max_count: usize,
+    pub regrow_range: f32,
+    pub regrow_hp: (f32, f32),
+    /// hp drained from food and turned into satiety per second of contact
+    pub eat_rate: f32,
+    /// hp gained by plants per second, up to `max_hp`
+    pub growth_rate: f32,
+    pub max_hp: f32,
+    /// hp from which a plant drops a seed every `seed_interval` seconds
+    pub seed_hp: f32,
+    pub seed_interval: f32,
+    pub seed_distance: f32,
+    /// hp moved from the plant into its seed
+    pub seed_cost: f32,
+    /// hp of a carcass is `carcass_hp + carcass_satiety * satiety` of the dead sectarian
+    pub carcass_hp: f32,
+    pub carcass_satiety: f32,
+    /// seconds until a carcass rots away
+    pub carcass_decay: f32,
+    pub grass: FoodKindConfig,
+    pub fruit: FoodKindConfig,
+    pub carcass: FoodKindConfig,
+    pub poison: FoodKindConfig,
+}
+impl Default for FoodConfig {
+    fn default() -> Self {
+        Self {
+            initial_count: 1_000, initial_range: 4_000., initial_hp: (0.7, 0.9),
+            max_count: 6_000, regrow_range: 8_000., regrow_hp: (0.2, 0.5),
+            eat_rate: 1.,
+            growth_rate: 0.01, max_hp: 1.5,
+            seed_hp: 1., seed_interval: 30., seed_distance: 150., seed_cost: 0.1,
+            carcass_hp: 0.2, carcass_satiety: 0.5, carcass_decay: 60.,
+            grass: FoodKindConfig::default(),
+            fruit: FoodKindConfig { share: 0.2, hp: 0.6, nutrition: 2., toxic: 0., color: (40., 0.7, 0.8) },
+            carcass: FoodKindConfig { share: 0., hp: 1., nutrition: 1.5, toxic: 0., color: (0., 0.5, 0.45) },
+            poison: FoodKindConfig { share: 0.1, hp: 1., nutrition: 0.3, toxic: 0.5, color: (280., 0.5, 0.6) },
+        }
+    }
+}
+impl FoodConfig {
+    pub fn kind(&self, kind: FoodKind) -> &FoodKindConfig {
+        match kind {
+            FoodKind::Grass => &self.grass,
+            FoodKind::Fruit => &self.fruit,
+            FoodKind::Carcass => &self.carcass,
+            FoodKind::Poison => &self.poison,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FoodKindConfig {
+    /// relative chance of a new plant to be of this kind
+    pub share: f32,
+    /// multiplier of spawn hp, `max_hp` and `seed_hp`
+    pub hp: f32,
+    /// satiety gained per hp eaten
+    pub nutrition: f32,
+    /// hp lost per hp eaten
+    pub toxic: f32,
+    /// hue, saturation and value
+    pub color: (f32, f32, f32),
+}
+impl Default for FoodKindConfig {
+    fn default() -> Self {
+        Self { share: 1., hp: 1., nutrition: 1., toxic: 0., color: (120., 0.2, 0.6) }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SectariiConfig {
+    /// only read at startup
+    pub population: usize,
+    /// only read at startup
+    pub species: usize,
+    pub spawn_range: f32,
+    pub reproduction_threshold: f32,
+    pub reproduction_cost: f32,
+    pub offspring_distance: f32,
+    /// sight range of the first generation, later evolved by the [`Body`](crate::body::Body)
+    pub sight_range: f32,
+    /// rays cast by the sight sensor, only read at startup
+    pub sight_rays: usize,
+    /// angle in radians covered by the sight rays of the first generation
+    pub sight_fov: f32,
+    pub force: f32,
+    pub torque: f32,
+    /// hp drained from the prey per second of contact
+    pub predation_rate: f32,
+    /// share of the drained hp the predator gains as satiety
+    pub predation_satiety: f32,
+    /// share of the drained hp the predator gains as hp
+    pub predation_hp: f32,
+    /// whether sectarii can drain or shoot members of their own species
+    pub same_species_predation: bool,
+}
+impl Default for SectariiConfig {
+    fn default() -> Self {
+        Self {
+            population: 2_000, species: 8, spawn_range: 6_000.,
+            reproduction_threshold: 1.75, reproduction_cost: 1., offspring_distance: 128.,
+            sight_range: 180., sight_rays: 7, sight_fov: 2., force: 3., torque: 4.,
+            predation_rate: 1., predation_satiety: 0.3, predation_hp: 0.7, same_species_predation: true
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BulletsConfig {
+    /// network output above which a bullet is fired
+    pub threshold: f32,
+    /// stamina spent per shot
+    pub cost: f32,
+    /// seconds between shots
+    pub cooldown: f32,
+    pub speed: f32,
+    /// seconds a bullet flies before it vanishes
+    pub lifetime: f32,
+    /// hp taken from the sectarian that gets hit
+    pub damage: f32,
+}
+impl Default for BulletsConfig {
+    fn default() -> Self {
+        Self { threshold: 0.5, cost: 0.4, cooldown: 1., speed: 300., lifetime: 1., damage: 0.25 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    /// seconds between automatic world snapshots, 0 disables them
+    pub autosave: f32,
+    pub path: PathBuf,
+}
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self { autosave: 600., path: PathBuf::from("assets/snapshot.ron") }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct StatsConfig {
+    /// simulated seconds between samples
+    pub interval: f32,
+    /// csv file the samples are written to, empty disables it
+    pub path: PathBuf,
+    /// samples kept in memory
+    pub history: usize,
+}
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self { interval: 10., path: PathBuf::from("assets/stats.csv"), history: 2_000 }
+    }
+}
+
+impl SimConfig {
+    /// Reads a TOML file and applies `section.key=value` overrides on top of it.
+    pub fn load(path: &Path, overrides: &[String]) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let table = toml::from_str::<toml::Table>(&text).map_err(|e| e.to_string())?;
+        Self::from_table(table, overrides)
+    }
+
+    fn from_table(mut table: toml::Table, overrides: &[String]) -> Result<Self, String> {
+        for o in overrides {
+            let (key, raw) = o.split_once('=').ok_or(format!("override `{}` is not key=value", o))?;
+            let value = toml::from_str::<toml::Table>(&format!("v = {}", raw)).ok()
+                .and_then(|mut t| t.remove("v"))
+                .unwrap_or(toml::Value::String(raw.to_string()));
+
+            let mut path = key.trim().split('.').collect::<Vec<_>>();
+            let last = path.pop().unwrap();
+            let mut t = &mut table;
+            for p in path {
+                t = t.entry(p).or_insert(toml::Value::Table(toml::Table::new()))
+                    .as_table_mut().ok_or(format!("override `{}`: `{}` is not a table", o, p))?;
+            }
+            t.insert(last.to_string(), value);
+        }
+        table.try_into::<Self>().map_err(|e| e.to_string())?.validate()
+    }
+
+    /// Rejects values the simulation would panic on, so that a bad hot reload keeps the previous config.
+    fn validate(self) -> Result<Self, String> {
+        let bounds = [
+            ("food.initial_hp", self.food.initial_hp), ("food.regrow_hp", self.food.regrow_hp),
+            ("fertility.hotspot_radius", self.fertility.hotspot_radius), ("map.rock_radius", self.map.rock_radius),
+            ("body.size", self.body.size), ("body.density", self.body.density), ("body.sight_range", self.body.sight_range),
+            ("body.sight_fov", self.body.sight_fov), ("body.steal_radius", self.body.steal_radius),
+        ];
+        if let Some((key, _)) = bounds.iter().find(|(_, b)| !(b.0.is_finite() && b.1.is_finite()) || b.0 > b.1 ) {
+            return Err(format!("`{}` must be [min, max] with min <= max", key))
+        }
+        let ranges = [
+            ("food.initial_range", self.food.initial_range), ("food.regrow_range", self.food.regrow_range),
+            ("sectarii.spawn_range", self.sectarii.spawn_range), ("fertility.hotspot_range", self.fertility.hotspot_range),
+            ("map.rock_range", self.map.rock_range), ("physics.relative_speed", self.physics.relative_speed),
+            ("stats.interval", self.stats.interval), ("body.speciation_drift", self.body.speciation_drift),
+        ];
+        if let Some((key, _)) = ranges.iter().find(|(_, r)| !(r.is_finite() && *r >= 0.) ) {
+            return Err(format!("`{}` must be zero or more", key))
+        }
+        if !(self.physics.fixed_hz.is_finite() && self.physics.fixed_hz > 0.) {
+            return Err("`physics.fixed_hz` must be more than zero".to_string())
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Resource)]
+struct ConfigWatcher {
+    path: Option<PathBuf>,
+    overrides: Vec<String>,
+    modified: Option<SystemTime>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn hot_reload(
+    mut watcher: ResMut<ConfigWatcher>,
+    mut config: ResMut<SimConfig>,
+){
+    let Some(path) = watcher.path.clone() else { return };
+    let m = modified(&path);
+    if m.is_none() || m == watcher.modified { return }
+    watcher.modified = m;
+
+    match SimConfig::load(&path, &watcher.overrides) {
+        Ok(c) => {
+            info!("config reloaded from {:?}", path);
+            *config = c;
+        }
+        Err(e) => warn!("config {:?} not reloaded: {}", path, e),
+    }
+}
+
+fn apply_physics(
+    config: Res<SimConfig>,
+    mut fixed: ResMut<Time<Fixed>>,
+    mut physics: ResMut<Time<Physics>>,
+    mut substeps: ResMut<SubstepCount>,
+){
+    fixed.set_timestep_hz(config.physics.fixed_hz);
+    physics.set_relative_speed(config.physics.relative_speed);
+    substeps.0 = config.physics.substeps;
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LineageConfig {
+    /// seconds between automatic exports, 0 disables them
+    pub autosave: f32,
+    /// exported as `.nwk` and `.json` next to this path, empty disables exports
+    pub path: PathBuf,
+}
+impl Default for LineageConfig {
+    fn default() -> Self {
+        Self { autosave: 600., path: PathBuf::from("assets/lineage") }
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{events::DeathEvent, lineage::Lineage, sectarii::{Brain, Sectarian, Species}, Hp, SimSet};
+
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(DeathStats::default())
+            .add_systems(FixedUpdate, death_system.in_set(SimSet::Death).before(crate::hp_system))
+        ;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DeathCause {
+    Starvation,
+    /// moving on an empty stomach
+    Exhaustion,
+    /// drained by the steal sensor of another sectarian
+    #[serde(alias = "Killed")]
+    Predation,
+    Shot,
+    Poison,
+}
+impl DeathCause {
+    pub const ALL: [DeathCause; 5] = [DeathCause::Starvation, DeathCause::Exhaustion, DeathCause::Predation, DeathCause::Shot, DeathCause::Poison];
+}
+
+/// Hp lost over the lifetime, by cause and by attacker.
+#[derive(Component, Clone, Default)]
+pub struct Damage {
+    pub taken: [f32; DeathCause::ALL.len()],
+    /// attacker entity and network key with the hp they took
+    pub attackers: Vec<(Entity, usize, f32)>,
+}
+impl Damage {
+    pub fn add(&mut self, cause: DeathCause, hp: f32) {
+        self.taken[cause as usize] += hp;
+    }
+
+    pub fn attack(&mut self, cause: DeathCause, attacker: Entity, key: usize, hp: f32) {
+        self.add(cause, hp);
+        match self.attackers.iter_mut().find(|a| a.0 == attacker ) {
+            Some(a) => a.2 += hp,
+            None => self.attackers.push((attacker, key, hp)),
+        }
+    }
+
+    pub fn dominant(&self) -> DeathCause {
+        DeathCause::ALL.into_iter().max_by(|a, b| self.taken[*a as usize].total_cmp(&self.taken[*b as usize]) ).unwrap()
+    }
+
+    /// Attacker that took the most hp.
+    pub fn killer(&self) -> Option<(Entity, usize)> {
+        self.attackers.iter().max_by(|a, b| a.2.total_cmp(&b.2) ).map(|a| (a.0, a.1) )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SpeciesDeaths {
+    /// deaths of members, by cause
+    pub causes: BTreeMap<DeathCause, usize>,
+    /// kills made by members, by the species of the victim
+    pub kills: BTreeMap<usize, usize>,
+}
+
+/// Deaths since the start of the run, per species.
+#[derive(Resource, Serialize, Deserialize, Clone, Default)]
+pub struct DeathStats(pub BTreeMap<usize, SpeciesDeaths>);
+
+pub(crate) fn death_system(
+    mut events: EventWriter<DeathEvent>,
+    mut stats: ResMut<DeathStats>,
+    lineage: Res<Lineage>,
+    sectarii: Query<(Entity, &Hp, &Brain, &Species, &Damage), With<Sectarian>>,
+){
+    sectarii.iter().filter(|s| s.1.0 <= 0. ).for_each(|(e, _, brain, s, damage)| {
+        let cause = damage.dominant();
+        let killer = damage.killer().filter(|_| matches!(cause, DeathCause::Predation | DeathCause::Shot) );
+        // by network key, the killer may have died already or come from a snapshot
+        let killer_species = killer.and_then(|k| lineage.records.get(&k.1) ).map(|r| r.species );
+
+        *stats.0.entry(s.0).or_default().causes.entry(cause).or_default() += 1;
+        if let Some(ks) = killer_species { *stats.0.entry(ks).or_default().kills.entry(s.0).or_default() += 1; }
+        events.send(DeathEvent { entity: e, key: brain.key, species: s.0, cause, killer, killer_species, fitness: brain.fitness });
+    } );
+}
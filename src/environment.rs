@@ -0,0 +1,84 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::SimConfig, SimSet};
+
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(Environment::default())
+            .add_systems(FixedUpdate, environment_system.before(SimSet::Senses))
+        ;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EnvironmentConfig {
+    /// the world stays at noon of an endless spring when disabled
+    pub enabled: bool,
+    /// seconds of a full day and night
+    pub day_length: f32,
+    /// share of the sight range left at midnight
+    pub night_sight: f32,
+    /// seconds of a full year
+    pub season_length: f32,
+    /// food regrowth is scaled by `1 + season_regrow * season`, summer being `season = 1`
+    pub season_regrow: f32,
+    /// satiety decay is scaled by `1 - season_metabolism * season`, so winter is hungrier
+    pub season_metabolism: f32,
+}
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            day_length: 240., night_sight: 0.4,
+            season_length: 2_400., season_regrow: 0.6, season_metabolism: 0.3,
+        }
+    }
+}
+
+/// World clock shared by every agent, in simulated seconds.
+#[derive(Resource, Clone, Default)]
+pub struct Environment {
+    pub elapsed: f32,
+}
+
+impl Environment {
+    /// 1 at noon, 0 at midnight.
+    pub fn daylight(&self, c: &EnvironmentConfig) -> f32 {
+        if !c.enabled || c.day_length <= 0. { return 1. }
+        0.5 + 0.5 * (self.elapsed / c.day_length * TAU).cos()
+    }
+
+    /// 1 in mid summer, -1 in mid winter.
+    pub fn season(&self, c: &EnvironmentConfig) -> f32 {
+        if !c.enabled || c.season_length <= 0. { return 0. }
+        (self.elapsed / c.season_length * TAU).sin()
+    }
+
+    pub fn sight(&self, c: &EnvironmentConfig) -> f32 {
+        c.night_sight + (1. - c.night_sight) * self.daylight(c)
+    }
+
+    pub fn regrow(&self, c: &EnvironmentConfig) -> f32 {
+        (1. + c.season_regrow * self.season(c)).max(0.)
+    }
+
+    pub fn metabolism(&self, c: &EnvironmentConfig) -> f32 {
+        (1. - c.season_metabolism * self.season(c)).max(0.)
+    }
+}
+
+fn environment_system(
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    mut environment: ResMut<Environment>,
+){
+    if !config.environment.enabled { return }
+    environment.elapsed += time.delta_secs();
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+
+use crate::{death::DeathCause, food::FoodKind};
+
+/// Interactions of the simulation, so that stats, logging or effects can follow them from their own plugins.
+pub struct EventsPlugin;
+
+impl Plugin for EventsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<BirthEvent>()
+            .add_event::<DeathEvent>()
+            .add_event::<FeedEvent>()
+            .add_event::<AttackEvent>()
+            .add_event::<SpeciationEvent>()
+        ;
+    }
+}
+
+/// A sectarian was spawned, `parent` is `None` for the first generation.
+#[derive(Event, Clone, Debug)]
+pub struct BirthEvent {
+    pub entity: Entity,
+    pub key: usize,
+    pub species: usize,
+    pub parent: Option<(Entity, usize)>,
+    pub position: Vec2,
+}
+
+/// A sectarian's hp ran out, sent before it is despawned.
+#[derive(Event, Clone, Debug)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub key: usize,
+    pub species: usize,
+    pub cause: DeathCause,
+    pub killer: Option<(Entity, usize)>,
+    pub killer_species: Option<usize>,
+    pub fitness: (f32, f32),
+}
+
+/// Food hp eaten during one step.
+#[derive(Event, Clone, Debug)]
+pub struct FeedEvent {
+    pub entity: Entity,
+    pub key: usize,
+    pub food: Entity,
+    pub kind: FoodKind,
+    pub hp: f32,
+    /// satiety gained, after the nutrition of the kind
+    pub satiety: f32,
+}
+
+/// Hp taken from another sectarian during one step, by the steal sensor or a bullet.
+#[derive(Event, Clone, Debug)]
+pub struct AttackEvent {
+    pub attacker: Entity,
+    pub attacker_key: usize,
+    pub target: Entity,
+    pub target_key: usize,
+    pub cause: DeathCause,
+    pub hp: f32,
+}
+
+/// An offspring founded a species that didn't exist before.
+#[derive(Event, Clone, Debug)]
+pub struct SpeciationEvent {
+    pub species: usize,
+    pub parent_species: usize,
+    pub key: usize,
+}
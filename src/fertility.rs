@@ -0,0 +1,141 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use bevy::{image::{CompressedImageFormats, ImageSampler, ImageType}, prelude::*, render::render_asset::RenderAssetUsages};
+use noise::{NoiseFn, Perlin};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::SimConfig, map::Obstacles, world::WorldConfig, WorldSeed};
+
+pub struct FertilityPlugin;
+
+impl Plugin for FertilityPlugin {
+    fn build(&self, app: &mut App) {
+        let config = app.world().resource::<SimConfig>().fertility.clone();
+        let mut rng = app.world().resource::<WorldSeed>().rng(3);
+        let fertility = Fertility::new(&config, &mut rng);
+        app.insert_resource(fertility);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FertilityKind {
+    /// food is equally likely everywhere
+    Uniform,
+    Noise,
+    Hotspots,
+    /// brightness of a png/jpeg stretched over `image_size`
+    Image,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Hotspot {
+    pub center: (f32, f32),
+    pub radius: f32,
+    pub strength: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FertilityConfig {
+    pub kind: FertilityKind,
+    /// world units per noise period
+    pub noise_scale: f32,
+    /// fertility is `((noise - noise_floor) / (1 - noise_floor))`, so patches get sparser as the floor rises
+    pub noise_floor: f32,
+    pub hotspots: Vec<Hotspot>,
+    /// extra hotspots placed at random within `hotspot_range`, only read at startup
+    pub random_hotspots: usize,
+    pub hotspot_range: f32,
+    pub hotspot_radius: (f32, f32),
+    /// only read at startup
+    pub image: PathBuf,
+    /// full world extent covered by the image
+    pub image_size: (f32, f32),
+    /// regrowth candidates drawn per tick, each kept with a chance of the local fertility
+    pub attempts: usize,
+    /// radius in which neighbouring food slows regrowth down
+    pub density_radius: f32,
+    /// food within `density_radius` at which nothing regrows anymore, 0 disables the check
+    pub density_max: usize,
+}
+impl Default for FertilityConfig {
+    fn default() -> Self {
+        Self {
+            kind: FertilityKind::Uniform,
+            noise_scale: 4_000., noise_floor: 0.2,
+            hotspots: vec![], random_hotspots: 12, hotspot_range: 7_000., hotspot_radius: (300., 1_500.),
+            image: PathBuf::from("assets/fertility.png"), image_size: (16_000., 16_000.),
+            attempts: 1, density_radius: 200., density_max: 0,
+        }
+    }
+}
+
+/// Chance in `0..=1` for food to grow at a point.
+#[derive(Resource, Clone)]
+pub struct Fertility {
+    noise: Perlin,
+    hotspots: Vec<Hotspot>,
+    image: Option<(UVec2, Vec<f32>)>,
+}
+
+impl Fertility {
+    fn new(c: &FertilityConfig, rng: &mut impl Rng) -> Self {
+        let noise = Perlin::new(rng.random());
+        let mut hotspots = c.hotspots.clone();
+        hotspots.extend((0..c.random_hotspots).map(|_| Hotspot {
+            center: (rng.random_range(-c.hotspot_range..=c.hotspot_range), rng.random_range(-c.hotspot_range..=c.hotspot_range)),
+            radius: rng.random_range(c.hotspot_radius.0..=c.hotspot_radius.1),
+            strength: 1.,
+        } ));
+        let image = (c.kind == FertilityKind::Image).then(|| Self::load_image(&c.image)
+            .inspect_err(|e| error!("fertility {:?} not loaded: {}", c.image, e)).ok()
+        ).flatten();
+        Self { noise, hotspots, image }
+    }
+
+    fn load_image(path: &Path) -> Result<(UVec2, Vec<f32>), String> {
+        let data = fs::read(path).map_err(|e| e.to_string())?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+        let image = Image::from_buffer(&data, ImageType::Extension(ext), CompressedImageFormats::NONE, true,
+            ImageSampler::Default, RenderAssetUsages::MAIN_WORLD).map_err(|e| e.to_string())?;
+        let size = image.size();
+        let values = (0..size.y).flat_map(|y| (0..size.x).map(move |x| (x, y)) )
+            .map(|(x, y)| image.get_color_at(x, y).map(|c| c.to_srgba().luminance()).unwrap_or(0.) )
+            .collect();
+        Ok((size, values))
+    }
+
+    pub fn value(&self, c: &FertilityConfig, p: Vec2) -> f32 {
+        match c.kind {
+            FertilityKind::Uniform => 1.,
+            FertilityKind::Noise => {
+                let q = p / c.noise_scale.max(1.);
+                let n = self.noise.get([q.x as f64, q.y as f64]) as f32 * 0.5 + 0.5;
+                ((n - c.noise_floor) / (1. - c.noise_floor).max(f32::EPSILON)).clamp(0., 1.)
+            }
+            FertilityKind::Hotspots => self.hotspots.iter()
+                .map(|h| h.strength * (-(p.distance(Vec2::from(h.center)) / h.radius.max(1.)).powi(2)).exp() )
+                .fold(0., f32::max).clamp(0., 1.),
+            FertilityKind::Image => {
+                let Some((size, values)) = &self.image else { return 1. };
+                // image rows go top to bottom
+                let uv = p / Vec2::from(c.image_size) * Vec2::new(1., -1.) + 0.5;
+                if uv.min_element() < 0. || uv.max_element() >= 1. { return 0. }
+                let px = (uv * size.as_vec2()).as_uvec2();
+                values[(px.y * size.x + px.x) as usize]
+            }
+        }
+    }
+
+    /// Point within the world and outside obstacles, drawn in proportion to fertility as far as a few tries allow.
+    pub fn sample(&self, c: &FertilityConfig, world: &WorldConfig, obstacles: &Obstacles, rng: &mut impl Rng, range: f32) -> Vec2 {
+        let mut p = obstacles.sample(world, rng, range);
+        for _ in 0..32 {
+            if rng.random::<f32>() < self.value(c, p) { break }
+            p = obstacles.sample(world, rng, range);
+        }
+        p
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimConfig, events::{AttackEvent, BirthEvent, DeathEvent, FeedEvent},
+    sectarii::{Brain, Neat, Sectarian}, SimSet,
+};
+
+/// Turns interaction events into NEAT fitness through the [`Fitness`] resource.
+/// Insert your own `Fitness` after [`crate::SimulationPlugin`] to run an objective that isn't in [`FitnessKind`].
+pub struct FitnessPlugin;
+
+impl Plugin for FitnessPlugin {
+    fn build(&self, app: &mut App) {
+        let config = app.world().resource::<SimConfig>().fitness.clone();
+
+        app
+            .insert_resource(Fitness::new(&config))
+            .add_systems(FixedUpdate, tally_system.after(SimSet::Attacks).before(SimSet::Metabolism))
+            .add_systems(FixedUpdate, fitness_system.after(SimSet::Death))
+        ;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FitnessKind {
+    /// satiety gained from food plus half the hp taken from other sectarii
+    Energy,
+    /// seconds alive
+    Survival,
+    /// offspring spawned
+    Offspring,
+    /// cells of a grid visited for the first time, worth more the fewer agents went there before
+    Novelty,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FitnessConfig {
+    /// objective of the evolution, only read at startup
+    pub kind: FitnessKind,
+    /// side of the grid cells of the novelty objective
+    pub novelty_cell: f32,
+}
+impl Default for FitnessConfig {
+    fn default() -> Self {
+        Self { kind: FitnessKind::Energy, novelty_cell: 100. }
+    }
+}
+
+/// Objective of the evolution. Each event may reward one agent, returned as its network key and the fitness gained.
+pub trait FitnessFunction: Send + Sync {
+    fn birth(&mut self, _event: &BirthEvent) -> Option<(usize, f32)> { None }
+    fn feed(&mut self, _event: &FeedEvent) -> Option<(usize, f32)> { None }
+    fn attack(&mut self, _event: &AttackEvent) -> Option<(usize, f32)> { None }
+    fn death(&mut self, _event: &DeathEvent) {}
+    /// Called every step for every living agent, returns the fitness gained.
+    fn tick(&mut self, _key: usize, _position: Vec2, _dt: f32) -> f32 { 0. }
+}
+
+#[derive(Resource)]
+pub struct Fitness(pub Box<dyn FitnessFunction>);
+
+impl Fitness {
+    pub fn new(c: &FitnessConfig) -> Self {
+        match c.kind {
+            FitnessKind::Energy => Self(Box::new(Energy)),
+            FitnessKind::Survival => Self(Box::new(Survival)),
+            FitnessKind::Offspring => Self(Box::new(Offspring)),
+            FitnessKind::Novelty => Self(Box::new(Novelty::new(c.novelty_cell))),
+        }
+    }
+}
+
+pub struct Energy;
+impl FitnessFunction for Energy {
+    fn feed(&mut self, event: &FeedEvent) -> Option<(usize, f32)> {
+        Some((event.key, event.satiety))
+    }
+
+    fn attack(&mut self, event: &AttackEvent) -> Option<(usize, f32)> {
+        Some((event.attacker_key, event.hp/2.))
+    }
+}
+
+pub struct Survival;
+impl FitnessFunction for Survival {
+    fn tick(&mut self, _key: usize, _position: Vec2, dt: f32) -> f32 {
+        dt
+    }
+}
+
+pub struct Offspring;
+impl FitnessFunction for Offspring {
+    fn birth(&mut self, event: &BirthEvent) -> Option<(usize, f32)> {
+        event.parent.map(|p| (p.1, 1.) )
+    }
+}
+
+pub struct Novelty {
+    cell: f32,
+    /// agents that ever entered each cell
+    visits: HashMap<IVec2, u32>,
+    /// cells entered by each living agent
+    visited: HashMap<usize, HashSet<IVec2>>,
+}
+impl Novelty {
+    pub fn new(cell: f32) -> Self {
+        Self { cell: cell.max(1.), visits: HashMap::new(), visited: HashMap::new() }
+    }
+}
+impl FitnessFunction for Novelty {
+    fn death(&mut self, event: &DeathEvent) {
+        self.visited.remove(&event.key);
+    }
+
+    fn tick(&mut self, key: usize, position: Vec2, _dt: f32) -> f32 {
+        let cell = (position / self.cell).floor().as_ivec2();
+        if !self.visited.entry(key).or_default().insert(cell) { return 0. }
+        let visits = self.visits.entry(cell).or_default();
+        *visits += 1;
+        1. / *visits as f32
+    }
+}
+
+/// Food and predation sums kept on [`Brain`] for stats and the lineage, whatever the objective.
+fn tally_system(
+    mut feeds: EventReader<FeedEvent>,
+    mut attacks: EventReader<AttackEvent>,
+    mut sectarii: Query<&mut Brain, With<Sectarian>>,
+){
+    feeds.read().for_each(|e| {
+        if let Ok(mut brain) = sectarii.get_mut(e.entity) { brain.fitness.0 += e.satiety/2.; }
+    } );
+    attacks.read().for_each(|e| {
+        if let Ok(mut brain) = sectarii.get_mut(e.attacker) { brain.fitness.1 += e.hp/2.; }
+    } );
+}
+
+fn fitness_system(
+    time: Res<Time>,
+    mut fitness: ResMut<Fitness>,
+    mut neat: ResMut<Neat>,
+    mut births: EventReader<BirthEvent>,
+    mut feeds: EventReader<FeedEvent>,
+    mut attacks: EventReader<AttackEvent>,
+    mut deaths: EventReader<DeathEvent>,
+    sectarii: Query<(&Brain, &Transform), With<Sectarian>>,
+){
+    let f = &mut fitness.0;
+    let mut gains = vec![];
+    gains.extend(births.read().filter_map(|e| f.birth(e) ));
+    gains.extend(feeds.read().filter_map(|e| f.feed(e) ));
+    gains.extend(attacks.read().filter_map(|e| f.attack(e) ));
+    deaths.read().for_each(|e| f.death(e) );
+    let dt = time.delta_secs();
+    gains.extend(sectarii.iter().map(|(brain, transform)| (brain.key, f.tick(brain.key, transform.translation.truncate(), dt)) ));
+
+    gains.into_iter().filter(|g| g.1 != 0. ).for_each(|(key, gain)| {
+        if let Some(a) = neat.0.agents.get_mut(&key) { a.fitness += gain; }
+    } );
+}
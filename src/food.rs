@@ -1,8 +1,9 @@
 use bevy::prelude::*;
 use avian2d::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::{GameLayer, Handlers, Hp};
+use crate::{config::{FoodConfig, SimConfig}, environment::Environment, fertility::Fertility, map::Obstacles, terrain::Terrain, GameLayer, Hp, SimRng, SimSet};
 
 pub struct FoodPlugin;
 
@@ -10,7 +11,7 @@ impl Plugin for FoodPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_systems(Startup, setup_food)
-            .add_systems(FixedUpdate, regrow_system)
+            .add_systems(FixedUpdate, (growth_system, regrow_system, decay_system).chain().in_set(SimSet::Regrowth))
         ;
     }
 }
@@ -19,48 +20,159 @@ impl Plugin for FoodPlugin {
 #[derive(Component, Clone)]
 pub struct Food;
 
-fn setup_food(
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FoodKind {
+    #[default]
+    Grass,
+    Fruit,
+    Carcass,
+    Poison,
+}
+impl FoodKind {
+    pub const ALL: [FoodKind; 4] = [FoodKind::Grass, FoodKind::Fruit, FoodKind::Carcass, FoodKind::Poison];
+
+    /// Random plant kind, weighted by the configured shares; carcasses only come from dead sectarii.
+    pub fn pick_plant(c: &FoodConfig, rng: &mut impl Rng) -> Self {
+        let plants = [FoodKind::Grass, FoodKind::Fruit, FoodKind::Poison];
+        let total = plants.iter().map(|k| c.kind(*k).share.max(0.) ).sum::<f32>();
+        let mut r = rng.random::<f32>() * total;
+        for k in plants {
+            r -= c.kind(k).share.max(0.);
+            if r < 0. { return k }
+        }
+        FoodKind::Grass
+    }
+}
+
+/// Remains of a dead sectarian, the value is seconds until it rots away.
+#[derive(Component, Clone)]
+pub struct Carcass(pub f32);
+
+/// Seconds until a mature plant drops its next seed.
+#[derive(Component, Clone)]
+pub struct Plant(pub f32);
+
+pub(crate) fn setup_food(
     mut commands: Commands,
-    mut handlers: ResMut<Handlers>, 
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<SimConfig>,
+    obstacles: Res<Obstacles>,
+    fertility: Res<Fertility>,
+    mut rng: ResMut<SimRng>,
 ){
-    handlers.mesh_food = Some( meshes.add( Circle::new(3.) ) );
-    handlers.material_food = Some( materials.add(Color::hsv(120., 0.2, 0.6)) );
-
-    let mut rng = rand::rng();
-    (0..1_000).into_iter().for_each(|_|{
-        spawn_food(&mut commands, &handlers, 
-            Transform::from_xyz(rng.random_range(-4_000_f32..4_000_f32), rng.random_range(-4_000_f32..4_000_f32), 0.),
-            rng.random_range(0.7_f32..0.9_f32)
+    let c = &config.food;
+    let rng = &mut rng.0;
+    (0..c.initial_count).into_iter().for_each(|_|{
+        let kind = FoodKind::pick_plant(c, rng);
+        spawn_food(&mut commands, 
+            Transform::from_translation(fertility.sample(&config.fertility, &config.world, &obstacles, rng, c.initial_range).extend(0.)),
+            rng.random_range(c.initial_hp.0..=c.initial_hp.1) * c.kind(kind).hp,
+            kind, c.seed_interval
         );
     });
 }
 
 fn regrow_system(
     mut commands: Commands,
-    handlers: ResMut<Handlers>, 
+    config: Res<SimConfig>,
+    obstacles: Res<Obstacles>,
+    terrain: Res<Terrain>,
+    fertility: Res<Fertility>,
+    environment: Res<Environment>,
+    spatial: SpatialQuery,
+    mut rng: ResMut<SimRng>,
     entities: Query<&Food>,
 ){
-    let l = entities.iter().len();
-    if l < 6_000 {
-        let mut rng = rand::rng();
-        spawn_food(&mut commands, &handlers, 
-            Transform::from_xyz(rng.random_range(-8_000_f32..8_000_f32), rng.random_range(-8_000_f32..8_000_f32), 0.),
-            rng.random_range(0.2_f32..0.5_f32)
+    let c = &config.food;
+    let f = &config.fertility;
+    let mut l = entities.iter().len();
+    let rng = &mut rng.0;
+    let area = Collider::circle(f.density_radius);
+    let filter = SpatialQueryFilter::from_mask(GameLayer::Foods);
+    for _ in 0..f.attempts {
+        if l >= c.max_count { break }
+        let p = obstacles.sample(&config.world, rng, c.regrow_range);
+        let mut chance = fertility.value(f, p) * environment.regrow(&config.environment) * terrain.regrow(&config.terrain, p);
+        if f.density_max > 0 {
+            let near = spatial.shape_intersections(&area, p, 0., &filter).len();
+            chance *= 1. - (near as f32 / f.density_max as f32).min(1.);
+        }
+        if rng.random::<f32>() >= chance { continue }
+        let kind = FoodKind::pick_plant(c, rng);
+        spawn_food(&mut commands, 
+            Transform::from_translation(p.extend(0.)),
+            rng.random_range(c.regrow_hp.0..=c.regrow_hp.1) * c.kind(kind).hp,
+            kind, c.seed_interval
         );
-    } 
+        l += 1;
+    }
+}
+
+// plants grow toward `max_hp` of their kind, the body follows so that its area matches the hp
+fn growth_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    obstacles: Res<Obstacles>,
+    fertility: Res<Fertility>,
+    environment: Res<Environment>,
+    mut rng: ResMut<SimRng>,
+    mut plants: Query<(&mut Hp, &mut Plant, &mut Transform, &FoodKind), With<Food>>,
+){
+    let c = &config.food;
+    let dt = time.delta_secs();
+    let growth = c.growth_rate * environment.regrow(&config.environment);
+    let rng = &mut rng.0;
+    let mut count = plants.iter().len();
+    plants.iter_mut().for_each(|(mut hp, mut plant, mut transform, kind)| {
+        let k = c.kind(*kind);
+        if hp.0 < c.max_hp * k.hp { hp.0 = (hp.0 + dt * growth * k.hp).min(c.max_hp * k.hp); }
+        // rescaling a collider touches the broadphase, so the body only follows in 5% steps
+        let scale = hp.0.max(0.05).sqrt();
+        if (transform.scale.x - scale).abs() > 0.05 * scale { transform.scale = Vec3::splat(scale); }
+
+        if hp.0 < c.seed_hp * k.hp { return }
+        plant.0 -= dt;
+        if plant.0 > 0. { return }
+        plant.0 = c.seed_interval;
+        if count >= c.max_count { return }
+
+        let p = transform.translation.truncate() + Vec2::from_angle(rng.random::<f32>() * std::f32::consts::TAU) * rng.random::<f32>() * c.seed_distance;
+        if !config.world.contains(p) || obstacles.contains(p) || rng.random::<f32>() >= fertility.value(&config.fertility, p) { return }
+        hp.0 -= c.seed_cost;
+        spawn_food(&mut commands, Transform::from_translation(p.extend(0.)), c.seed_cost, *kind, c.seed_interval);
+        count += 1;
+    } );
+}
+
+fn decay_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut carcasses: Query<(Entity, &mut Carcass)>,
+){
+    carcasses.iter_mut().for_each(|(e, mut carcass)| {
+        carcass.0 -= time.delta_secs();
+        if carcass.0 <= 0. { commands.entity(e).despawn_recursive(); }
+    } );
 }
 
-fn spawn_food(commands: &mut Commands, handlers: &ResMut<Handlers>, transform: Transform, hp: f32){
-    commands.spawn((
+fn food_body(transform: Transform, hp: f32, kind: FoodKind) -> impl Bundle {
+    (
         Food,
+        kind,
         Hp(hp),
-        Mesh2d( handlers.mesh_food.clone().unwrap() ),
-        MeshMaterial2d( handlers.material_food.clone().unwrap() ),
         RigidBody::Static,
         Collider::circle(3.),
         CollisionLayers::new([GameLayer::Default, GameLayer::Foods], [GameLayer::Sectarii, GameLayer::Foods, GameLayer::Bullets]),
         transform
-    ));
+    )
+}
+
+/// `seed` is the number of seconds until the plant drops its first seed once mature.
+pub fn spawn_food(commands: &mut Commands, transform: Transform, hp: f32, kind: FoodKind, seed: f32){
+    commands.spawn((food_body(transform, hp, kind), Plant(seed)));
+}
+
+pub fn spawn_carcass(commands: &mut Commands, transform: Transform, hp: f32, decay: f32){
+    commands.spawn((food_body(transform, hp, FoodKind::Carcass), Carcass(decay)));
 }
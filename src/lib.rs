@@ -0,0 +1,251 @@
+use std::{path::PathBuf, time::Duration};
+
+use bevy::{app::ScheduleRunnerPlugin, math::ops::atan2, prelude::*};
+use avian2d::{math::PI, prelude::*};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+pub mod sectarii;
+use sectarii::{Brain, SectariiPlugin};
+pub mod food;
+use food::{spawn_carcass, Food, FoodPlugin};
+pub mod config;
+use config::{ConfigPlugin, SimConfig};
+pub mod bullets;
+use bullets::BulletsPlugin;
+pub mod world;
+use world::WorldPlugin;
+pub mod map;
+use map::MapPlugin;
+pub mod body;
+use body::Body;
+pub mod environment;
+use environment::{Environment, EnvironmentPlugin};
+pub mod fertility;
+use fertility::FertilityPlugin;
+pub mod terrain;
+use terrain::{TerrainPlugin, Zone};
+pub mod snapshot;
+use snapshot::SnapshotPlugin;
+pub mod stats;
+use stats::StatsPlugin;
+pub mod events;
+use events::EventsPlugin;
+pub mod death;
+use death::{Damage, DeathCause, DeathPlugin};
+pub mod lineage;
+use lineage::LineagePlugin;
+pub mod fitness;
+use fitness::FitnessPlugin;
+
+pub use rusty_neat;
+
+/// Everything needed to run the evolution: physics setup, metabolism, food and agents.
+/// Rendering and UI are left to the frontend.
+#[derive(Default)]
+pub struct SimulationPlugin {
+    /// TOML file with a [`SimConfig`], watched for changes while running
+    pub config: Option<PathBuf>,
+    /// `section.key=value` entries applied on top of the config file
+    pub overrides: Vec<String>,
+    /// world snapshot to resume from
+    pub snapshot: Option<PathBuf>,
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ConfigPlugin { path: self.config.clone(), overrides: self.overrides.clone() });
+        let physics = app.world().resource::<SimConfig>().physics.clone();
+        // a resumed run is rebuilt in the world of its snapshot
+        let seed = self.snapshot.as_deref().and_then(snapshot::world_seed)
+            .or(app.world().resource::<SimConfig>().seed).unwrap_or_else(rand::random);
+        info!("simulation seed: {}", seed);
+
+        app
+            .insert_resource(SimRng(ChaCha12Rng::seed_from_u64(seed)))
+            .insert_resource(WorldSeed(seed))
+            .configure_sets(FixedUpdate, (SimSet::Senses, SimSet::Interactions, SimSet::Attacks, SimSet::Metabolism, SimSet::Reproduction, SimSet::Regrowth, SimSet::Death).chain())
+            .insert_resource(Time::<Fixed>::from_hz(physics.fixed_hz))
+            .add_plugins(PhysicsPlugins::default())
+            .insert_resource(Time::<Physics>::default().with_relative_speed(physics.relative_speed))
+            .insert_resource(SubstepCount(physics.substeps))
+            //.add_plugins(PhysicsDebugPlugin::default())
+            .insert_resource(Gravity(Vec2::ZERO))
+            .add_plugins(EventsPlugin)
+            .add_plugins(WorldPlugin)
+            .add_plugins(MapPlugin)
+            .add_plugins(TerrainPlugin)
+            .add_plugins(FertilityPlugin)
+            .add_plugins(EnvironmentPlugin)
+            .add_plugins(FoodPlugin)
+            .add_plugins(SectariiPlugin)
+            .add_plugins(BulletsPlugin)
+            .add_plugins(SnapshotPlugin { load: self.snapshot.clone() })
+            .add_plugins(StatsPlugin { append: self.snapshot.is_some() })
+            .add_plugins(DeathPlugin)
+            .add_plugins(LineagePlugin)
+            .add_plugins(FitnessPlugin)
+            .add_systems(FixedUpdate, (satiety_system, stamina_system).chain().in_set(SimSet::Metabolism))
+            .add_systems(FixedUpdate, hp_system.in_set(SimSet::Death))
+        ;
+    }
+}
+
+/// Source of the simulation's randomness, seeded from [`SimConfig::seed`] and carried over in snapshots.
+/// Not deterministic as a whole: network weights, mutations and species assignment inside `rusty_neat`
+/// draw from that crate's own generator, which takes no seed.
+#[derive(Resource)]
+pub struct SimRng(pub ChaCha12Rng);
+
+/// Seed the map, terrain and fertility are generated from, kept in snapshots so that a resumed run gets the same world.
+#[derive(Resource, Clone, Copy)]
+pub struct WorldSeed(pub u64);
+
+impl WorldSeed {
+    /// Generator of one part of the world, independent of the other parts and of [`SimRng`].
+    pub fn rng(&self, stream: u64) -> ChaCha12Rng {
+        let mut rng = ChaCha12Rng::seed_from_u64(self.0);
+        rng.set_stream(stream);
+        rng
+    }
+}
+
+/// Fixed order of the simulation step, so that runs with the same seed don't depend on scheduling.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SimSet {
+    Senses,
+    Interactions,
+    Attacks,
+    Metabolism,
+    Reproduction,
+    Regrowth,
+    Death,
+}
+
+/// Bare minimum of engine plugins to run [`SimulationPlugin`] without a window or renderer.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins((
+                MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1. / 60.))),
+                TransformPlugin,
+                HierarchyPlugin,
+                bevy::log::LogPlugin::default(),
+                AssetPlugin::default(),
+                bevy::scene::ScenePlugin,
+            ))
+            .init_asset::<Mesh>()
+        ;
+    }
+}
+
+#[derive(PhysicsLayer, Default)]
+pub enum GameLayer {
+    #[default]
+    Default,
+    Sectarii,
+    Foods,
+    Bullets,
+    Walls,
+    Obstacles,
+}
+
+
+#[derive(Component, Clone)]
+pub struct Hp(pub f32);
+fn hp_system(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    mut entities: Query<(Entity, &mut Hp, &Transform, Option<&Satiety>)>,
+){
+    let c = &config.food;
+    entities.iter_mut().for_each(|(e, mut hp, transform, satiety)| {
+        hp.0 = hp.0.clamp(0., 2.);
+        if hp.0 <= 0_f32 {
+            commands.entity(e).despawn_recursive();
+            // only sectarii have satiety, their bodies stay behind as food
+            if let Some(satiety) = satiety {
+                let nutrition = c.carcass_hp + satiety.0 * c.carcass_satiety;
+                if nutrition > 0. {
+                    spawn_carcass(&mut commands, Transform::from_translation(transform.translation), nutrition, c.carcass_decay);
+                }
+            }
+        }
+    } );
+}
+
+#[derive(Component, Clone)]
+pub struct Satiety(pub f32);
+fn satiety_system(
+    time: Res<Time>, 
+    config: Res<SimConfig>,
+    environment: Res<Environment>,
+    mut entities: Query<(&mut Hp, &mut Satiety, &mut Damage, &Body), Without<Food>>
+){
+    let c = &config.metabolism;
+    let decay = environment.metabolism(&config.environment) / c.satiety_decay;
+    entities.par_iter_mut().for_each(|(mut hp, mut satiety, mut damage, body)| {
+        satiety.0 -= time.delta_secs() * decay * body.metabolism(&config.body);
+        if satiety.0 <= 0_f32 {
+            hp.0 -= time.delta_secs() / c.starvation;
+            damage.add(DeathCause::Starvation, time.delta_secs() / c.starvation);
+        }
+        satiety.0 = satiety.0.clamp(0., 2.);
+    } );
+}
+
+#[derive(Component, Clone)]
+pub struct Stamina(pub f32);
+fn stamina_system(
+    time: Res<Time>, 
+    config: Res<SimConfig>,
+    mut entities: Query<(&mut Stamina, &mut Satiety, &mut Hp, &mut Damage, &Brain, &Zone)>
+){
+    let c = &config.metabolism;
+    entities.par_iter_mut().for_each(|(mut stamina, mut satiety, mut hp, mut damage, brain, zone)| {
+        if brain.linvel < c.stamina_free_speed { stamina.0 = (stamina.0 + time.delta_secs() * c.stamina_regen).clamp(0., 2.);}
+        else { 
+            let cost = c.stamina_cost * config.terrain.zone(zone.0).stamina_cost;
+            stamina.0 -= (brain.linvel - c.stamina_cost_offset).powi(2) * time.delta_secs() * cost;
+            if stamina.0 < 0. {
+                if satiety.0 > 0. { satiety.0 += stamina.0 }
+                else { hp.0 += stamina.0; damage.add(DeathCause::Exhaustion, -stamina.0); }
+            }
+            stamina.0 = stamina.0.clamp(0., 2.)
+        }
+    } );
+}
+
+
+pub fn angle_between_2d(transform_a: &Transform, transform_b: &Transform) -> f32 {
+    let dir_forward = transform_a.local_y().normalize_or_zero();
+    let dir_ab = (transform_b.translation - transform_a.translation).normalize_or_zero();
+
+    let angle_forward = atan2(dir_forward.y, dir_forward.x);
+    let angle_direction = atan2(dir_ab.y, dir_ab.x);
+    let angle_diff = angle_direction - angle_forward;
+
+    let angle = angle_diff % (2. * PI);
+    if angle >= PI {return angle - 2. * PI}
+    else if angle < -PI {return angle + 2. * PI}
+    angle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angle_between_2d_is_relative_to_local_y() {
+        let a = Transform::from_xyz(0., 0., 0.);
+        [
+            ((0., 10.), 0.), ((10., 10.), -PI / 4.), ((10., 0.), -PI / 2.), ((10., -10.), -3. * PI / 4.),
+            ((0., -10.), -PI), ((-10., -10.), 3. * PI / 4.), ((-10., 0.), PI / 2.), ((-10., 10.), PI / 4.),
+        ].into_iter().for_each(|((x, y), expected)| {
+            let angle = angle_between_2d(&a, &Transform::from_xyz(x, y, 0.));
+            assert!((angle - expected).abs() < 1e-5, "({}, {}): {} != {}", x, y, angle, expected);
+        } );
+    }
+}
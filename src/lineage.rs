@@ -0,0 +1,240 @@
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::{Path, PathBuf}, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::SimConfig, death::DeathCause, events::DeathEvent, sectarii::{Brain, Neat, Sectarian, Species}, SimSet};
+
+pub struct LineagePlugin;
+
+impl Plugin for LineagePlugin {
+    fn build(&self, app: &mut App) {
+        let autosave = app.world().resource::<SimConfig>().lineage.autosave;
+
+        app
+            .insert_resource(Lineage::default())
+            .add_event::<ExportLineage>()
+            .add_systems(FixedUpdate, clock_system.before(SimSet::Senses))
+            .add_systems(FixedUpdate, death_system.in_set(SimSet::Death).after(crate::death::death_system))
+            .add_systems(FixedUpdate, species_system.after(SimSet::Death))
+            .add_systems(PreUpdate, export_system)
+            .add_systems(Last, exit_system)
+        ;
+        if autosave > 0. {
+            app.add_systems(Update, autosave_system.run_if(on_timer(Duration::from_secs_f32(autosave))));
+        }
+    }
+}
+
+/// Writes the lineage next to the given path, as `.nwk` (Newick) and `.json`.
+#[derive(Event)]
+pub struct ExportLineage(pub PathBuf);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LineageRecord {
+    pub key: usize,
+    pub parent: Option<usize>,
+    pub species: usize,
+    pub birth: f32,
+    pub death: Option<f32>,
+    pub cause: Option<DeathCause>,
+    /// food and predation fitness summed over the lifetime, updated on death and export
+    pub fitness: f32,
+    /// NEAT fitness under the `fitness.kind` objective, the one selection used
+    #[serde(default)]
+    pub objective: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpeciesRecord {
+    pub species: usize,
+    /// species of the parent of the first member
+    pub parent: Option<usize>,
+    pub founded: f32,
+    /// last time the species had no living member, cleared again if it comes back
+    pub extinct: Option<f32>,
+    /// living members as of the last simulation step
+    #[serde(skip)]
+    pub population: usize,
+}
+
+/// Every agent ever spawned, by network key. Times are simulated seconds since the first generation.
+#[derive(Resource, Serialize, Deserialize, Clone, Default)]
+pub struct Lineage {
+    pub elapsed: f32,
+    pub records: BTreeMap<usize, LineageRecord>,
+    #[serde(default)]
+    pub species: BTreeMap<usize, SpeciesRecord>,
+}
+
+impl Lineage {
+    pub fn birth(&mut self, key: usize, parent: Option<usize>, species: usize) {
+        let parent_species = parent.and_then(|p| self.records.get(&p) ).map(|r| r.species ).filter(|s| *s != species );
+        let elapsed = self.elapsed;
+        self.species.entry(species).or_insert_with(|| SpeciesRecord { species, parent: parent_species, founded: elapsed, extinct: None, population: 0 } );
+        self.records.insert(key, LineageRecord { key, parent, species, birth: self.elapsed, death: None, cause: None, fitness: 0., objective: 0. });
+    }
+
+    pub fn death(&mut self, key: usize, cause: DeathCause, fitness: f32, objective: f32) {
+        if let Some(r) = self.records.get_mut(&key) {
+            r.death = Some(self.elapsed);
+            r.cause = Some(cause);
+            r.fitness = fitness;
+            r.objective = objective;
+        }
+    }
+
+    /// Species in depth first order of the species tree, each with its depth.
+    pub fn species_tree(&self) -> Vec<(usize, usize)> {
+        let mut children = BTreeMap::<Option<usize>, Vec<usize>>::new();
+        self.species.values().for_each(|s| {
+            let parent = s.parent.filter(|p| self.species.contains_key(p) );
+            children.entry(parent).or_default().push(s.species);
+        } );
+        let mut out = vec![];
+        let mut stack = children.get(&None).map(|r| r.iter().rev().map(|s| (*s, 0)).collect::<Vec<_>>() ).unwrap_or_default();
+        while let Some((s, depth)) = stack.pop() {
+            out.push((s, depth));
+            if let Some(ch) = children.get(&Some(s)) { stack.extend(ch.iter().rev().map(|c| (*c, depth + 1)) ); }
+        }
+        out
+    }
+
+    pub fn children(&self) -> BTreeMap<usize, Vec<usize>> {
+        let mut children = BTreeMap::<usize, Vec<usize>>::new();
+        self.records.values().filter_map(|r| r.parent.map(|p| (p, r.key)) )
+            .for_each(|(p, k)| children.entry(p).or_default().push(k) );
+        children
+    }
+
+    /// Newick tree labelled with network keys, branch lengths are the time between the births of parent and child.
+    pub fn newick(&self) -> String {
+        enum Step { Enter(usize), Comma, Exit(usize) }
+
+        let children = self.children();
+        let label = |out: &mut String, k: usize| {
+            let r = &self.records[&k];
+            let parent = r.parent.and_then(|p| self.records.get(&p) ).map(|p| p.birth ).unwrap_or(r.birth);
+            let _ = write!(out, "{}:{:.2}", k, r.birth - parent);
+        };
+        let roots = self.records.values()
+            .filter(|r| r.parent.is_none_or(|p| !self.records.contains_key(&p)) )
+            .map(|r| r.key ).collect::<Vec<_>>();
+
+        // iterative, lineages easily get deeper than the stack
+        let mut out = String::from("(");
+        let mut stack = vec![];
+        let push = |stack: &mut Vec<Step>, keys: &[usize]| {
+            for (i, k) in keys.iter().enumerate().rev() {
+                stack.push(Step::Enter(*k));
+                if i > 0 { stack.push(Step::Comma); }
+            }
+        };
+        push(&mut stack, &roots);
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Comma => out.push(','),
+                Step::Exit(k) => { out.push(')'); label(&mut out, k); }
+                Step::Enter(k) => match children.get(&k) {
+                    Some(ch) => { out.push('('); stack.push(Step::Exit(k)); push(&mut stack, ch); }
+                    None => label(&mut out, k),
+                },
+            }
+        }
+        out.push_str(");");
+        out
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        if let Some(d) = path.parent() { fs::create_dir_all(d).map_err(|e| e.to_string())?; }
+        fs::write(path.with_extension("nwk"), self.newick()).map_err(|e| e.to_string())?;
+        let records = self.records.values().collect::<Vec<_>>();
+        let json = serde_json::to_string(&records).map_err(|e| e.to_string())?;
+        fs::write(path.with_extension("json"), json).map_err(|e| e.to_string())
+    }
+}
+
+fn clock_system(
+    time: Res<Time>,
+    mut lineage: ResMut<Lineage>,
+){
+    lineage.elapsed += time.delta_secs();
+}
+
+fn death_system(
+    mut lineage: ResMut<Lineage>,
+    neat: Res<Neat>,
+    mut events: EventReader<DeathEvent>,
+){
+    events.read().for_each(|e| {
+        let objective = neat.0.agents.get(&e.key).map(|a| a.fitness ).unwrap_or(0.);
+        lineage.death(e.key, e.cause, e.fitness.0 + e.fitness.1, objective);
+    } );
+}
+
+fn species_system(
+    mut lineage: ResMut<Lineage>,
+    sectarii: Query<&Species, With<Sectarian>>,
+){
+    let mut counts = BTreeMap::<usize, usize>::new();
+    sectarii.iter().for_each(|s| *counts.entry(s.0).or_default() += 1 );
+    let elapsed = lineage.elapsed;
+    lineage.species.values_mut().for_each(|s| {
+        s.population = counts.get(&s.species).copied().unwrap_or(0);
+        if s.population == 0 && s.extinct.is_none() { s.extinct = Some(elapsed); }
+        if s.population > 0 { s.extinct = None; }
+    } );
+}
+
+fn autosave_system(
+    config: Res<SimConfig>,
+    mut events: EventWriter<ExportLineage>,
+){
+    if config.lineage.path.as_os_str().is_empty() { return }
+    events.send(ExportLineage(config.lineage.path.clone()));
+}
+
+fn export_system(
+    mut events: EventReader<ExportLineage>,
+    mut lineage: ResMut<Lineage>,
+    neat: Res<Neat>,
+    sectarii: Query<&Brain, With<Sectarian>>,
+){
+    let Some(ExportLineage(path)) = events.read().last() else { return };
+    sectarii.iter().for_each(|brain| {
+        if let Some(r) = lineage.records.get_mut(&brain.key) {
+            r.fitness = brain.fitness.0 + brain.fitness.1;
+            if let Some(a) = neat.0.agents.get(&brain.key) { r.objective = a.fitness; }
+        }
+    } );
+    match lineage.export(path) {
+        Ok(_) => info!("lineage of {} agents exported to {:?}", lineage.records.len(), path),
+        Err(e) => error!("lineage {:?} not exported: {}", path, e),
+    }
+}
+
+fn exit_system(
+    exit: EventReader<AppExit>,
+    config: Res<SimConfig>,
+    lineage: Res<Lineage>,
+){
+    if exit.is_empty() || config.lineage.path.as_os_str().is_empty() { return }
+    if let Err(e) = lineage.export(&config.lineage.path) { error!("lineage {:?} not exported: {}", config.lineage.path, e); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newick_of_a_small_forest() {
+        let mut lineage = Lineage::default();
+        [(1, None, 0.), (2, Some(1), 1.), (3, Some(1), 2.5), (5, None, 3.), (4, Some(2), 4.), (6, Some(99), 5.)]
+            .into_iter().for_each(|(key, parent, birth)| {
+                lineage.elapsed = birth;
+                lineage.birth(key, parent, 0);
+            } );
+        // 6 has a parent that was never recorded, so it is a root next to 1 and 5
+        assert_eq!(lineage.newick(), "(((4:3.00)2:1.00,3:2.50)1:0.00,5:0.00,6:0.00);");
+    }
+}
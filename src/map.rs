@@ -0,0 +1,233 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use avian2d::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use usvg::{NodeExt, NodeKind, TreeParsing};
+
+use crate::{config::SimConfig, world::WorldConfig, GameLayer, WorldSeed};
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        let config = app.world().resource::<SimConfig>().clone();
+        let mut rng = app.world().resource::<WorldSeed>().rng(1);
+        let obstacles = Obstacles::generate(&config.map, &config.world, &mut rng);
+
+        app
+            .insert_resource(obstacles)
+            .add_systems(Startup, setup_obstacles)
+        ;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MapKind {
+    None,
+    /// round rocks scattered over the world
+    Rocks,
+    /// grid maze centered on the origin
+    Maze,
+    /// every path of an svg file becomes a wall
+    Svg,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MapConfig {
+    pub kind: MapKind,
+    pub rocks: usize,
+    pub rock_radius: (f32, f32),
+    pub rock_range: f32,
+    pub maze_cells: (usize, usize),
+    pub maze_cell: f32,
+    pub svg: PathBuf,
+    /// world units per svg unit
+    pub svg_scale: f32,
+    /// thickness of maze and svg walls
+    pub thickness: f32,
+}
+impl Default for MapConfig {
+    fn default() -> Self {
+        Self {
+            kind: MapKind::None,
+            rocks: 300, rock_radius: (40., 240.), rock_range: 8_000.,
+            maze_cells: (16, 16), maze_cell: 800.,
+            svg: PathBuf::from("assets/map.svg"), svg_scale: 10.,
+            thickness: 48.,
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+pub enum Obstacle {
+    Circle { center: Vec2, radius: f32 },
+    Rect { center: Vec2, half_size: Vec2 },
+    Segment { a: Vec2, b: Vec2, radius: f32 },
+}
+impl Obstacle {
+    pub fn contains(&self, p: Vec2) -> bool {
+        match self {
+            Obstacle::Circle { center, radius } => p.distance(*center) <= *radius,
+            Obstacle::Rect { center, half_size } => {
+                let d = (p - *center).abs();
+                d.x <= half_size.x && d.y <= half_size.y
+            }
+            Obstacle::Segment { a, b, radius } => {
+                let ab = *b - *a;
+                let t = ((p - *a).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0., 1.);
+                p.distance(*a + ab * t) <= *radius
+            }
+        }
+    }
+}
+
+/// Static obstacles of the current map, also used to keep spawns out of them.
+#[derive(Resource, Clone, Default)]
+pub struct Obstacles(pub Vec<Obstacle>);
+
+impl Obstacles {
+    pub fn contains(&self, p: Vec2) -> bool {
+        self.0.iter().any(|o| o.contains(p) )
+    }
+
+    /// Like [`WorldConfig::sample`], but outside of every obstacle when possible.
+    pub fn sample(&self, world: &WorldConfig, rng: &mut impl Rng, range: f32) -> Vec2 {
+        let mut p = world.sample(rng, range);
+        for _ in 0..16 {
+            if !self.contains(p) { break }
+            p = world.sample(rng, range);
+        }
+        p
+    }
+
+    fn generate(map: &MapConfig, world: &WorldConfig, rng: &mut impl Rng) -> Self {
+        match map.kind {
+            MapKind::None => Self::default(),
+            MapKind::Rocks => Self((0..map.rocks).map(|_| Obstacle::Circle {
+                center: world.sample(rng, map.rock_range),
+                radius: rng.random_range(map.rock_radius.0..=map.rock_radius.1),
+            } ).collect()),
+            MapKind::Maze => Self::maze(map, rng),
+            MapKind::Svg => Self::svg(map).unwrap_or_else(|e| {
+                error!("map {:?} not loaded: {}", map.svg, e);
+                Self::default()
+            }),
+        }
+    }
+
+    /// Recursive backtracker over a grid of cells, every wall left standing becomes an obstacle.
+    fn maze(map: &MapConfig, rng: &mut impl Rng) -> Self {
+        let (nx, ny) = (map.maze_cells.0.max(1), map.maze_cells.1.max(1));
+        let (cell, t) = (map.maze_cell, map.thickness);
+        // right[x][y] separates (x, y) from (x + 1, y), up[x][y] separates (x, y) from (x, y + 1)
+        let mut right = vec![vec![true; ny]; nx];
+        let mut up = vec![vec![true; ny]; nx];
+        let mut visited = vec![vec![false; ny]; nx];
+
+        let mut stack = vec![(0, 0)];
+        visited[0][0] = true;
+        while let Some(&(x, y)) = stack.last() {
+            let next = [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter()
+                .map(|(dx, dy)| (x as isize + dx, y as isize + dy))
+                .filter(|(nx_, ny_)| *nx_ >= 0 && *ny_ >= 0 && (*nx_ as usize) < nx && (*ny_ as usize) < ny)
+                .map(|(a, b)| (a as usize, b as usize))
+                .filter(|(a, b)| !visited[*a][*b])
+                .collect::<Vec<_>>();
+            if next.is_empty() { stack.pop(); continue }
+
+            let (a, b) = next[rng.random_range(0..next.len())];
+            if a > x { right[x][y] = false }
+            if a < x { right[a][b] = false }
+            if b > y { up[x][y] = false }
+            if b < y { up[a][b] = false }
+            visited[a][b] = true;
+            stack.push((a, b));
+        }
+
+        let origin = -Vec2::new(nx as f32, ny as f32) * cell / 2.;
+        let mut walls = vec![];
+        for x in 0..nx {
+            for y in 0..ny {
+                let corner = origin + Vec2::new(x as f32, y as f32) * cell;
+                if right[x][y] && x + 1 < nx {
+                    walls.push(Obstacle::Rect { center: corner + Vec2::new(cell, cell / 2.), half_size: Vec2::new(t, cell + t) / 2. });
+                }
+                if up[x][y] && y + 1 < ny {
+                    walls.push(Obstacle::Rect { center: corner + Vec2::new(cell / 2., cell), half_size: Vec2::new(cell + t, t) / 2. });
+                }
+            }
+        }
+        let size = Vec2::new(nx as f32, ny as f32) * cell;
+        walls.push(Obstacle::Rect { center: Vec2::new(0., origin.y), half_size: Vec2::new(size.x + t, t) / 2. });
+        walls.push(Obstacle::Rect { center: Vec2::new(0., -origin.y), half_size: Vec2::new(size.x + t, t) / 2. });
+        walls.push(Obstacle::Rect { center: Vec2::new(origin.x, 0.), half_size: Vec2::new(t, size.y + t) / 2. });
+        walls.push(Obstacle::Rect { center: Vec2::new(-origin.x, 0.), half_size: Vec2::new(t, size.y + t) / 2. });
+        Self(walls)
+    }
+
+    /// Curves are flattened to their end points, the svg is centered on the origin with y pointing up.
+    fn svg(map: &MapConfig) -> Result<Self, String> {
+        let data = fs::read(&map.svg).map_err(|e| e.to_string())?;
+        let tree = usvg::Tree::from_data(&data, &usvg::Options::default()).map_err(|e| e.to_string())?;
+        let center = Vec2::new(tree.size.width(), tree.size.height()) / 2.;
+        let to_world = |p: usvg::tiny_skia_path::Point| (Vec2::new(p.x, p.y) - center) * Vec2::new(1., -1.) * map.svg_scale;
+
+        let mut walls = vec![];
+        for node in tree.root.descendants() {
+            let kind = node.borrow();
+            let NodeKind::Path(ref path) = *kind else { continue };
+            let ts = node.abs_transform();
+            let mut start = None;
+            let mut last = None;
+            // `start` and `last` are in world space already
+            for segment in path.data.segments() {
+                use usvg::tiny_skia_path::PathSegment::*;
+                let mut p = match segment {
+                    MoveTo(p) => { start = None; last = None; p }
+                    LineTo(p) | QuadTo(_, p) | CubicTo(_, _, p) => p,
+                    Close => {
+                        if let (Some(a), Some(b)) = (last, start) {
+                            if a != b { walls.push(Obstacle::Segment { a, b, radius: map.thickness / 2. }); }
+                        }
+                        last = start;
+                        continue
+                    }
+                };
+                ts.map_point(&mut p);
+                let p = to_world(p);
+                if start.is_none() { start = Some(p) }
+                if let Some(a) = last {
+                    walls.push(Obstacle::Segment { a, b: p, radius: map.thickness / 2. });
+                }
+                last = Some(p);
+            }
+        }
+        Ok(Self(walls))
+    }
+}
+
+fn setup_obstacles(
+    mut commands: Commands,
+    obstacles: Res<Obstacles>,
+){
+    let layers = CollisionLayers::new([GameLayer::Default, GameLayer::Obstacles], [GameLayer::Sectarii, GameLayer::Foods, GameLayer::Bullets]);
+    obstacles.0.iter().for_each(|o| {
+        let (collider, transform) = match o {
+            Obstacle::Circle { center, radius } => (Collider::circle(*radius), Transform::from_translation(center.extend(0.))),
+            Obstacle::Rect { center, half_size } => (Collider::rectangle(half_size.x * 2., half_size.y * 2.), Transform::from_translation(center.extend(0.))),
+            Obstacle::Segment { a, b, radius } => {
+                let ab = *b - *a;
+                (
+                    Collider::capsule(*radius, ab.length()),
+                    Transform::from_translation(((*a + *b) / 2.).extend(0.))
+                        .with_rotation(Quat::from_rotation_z(ab.to_angle() - std::f32::consts::FRAC_PI_2)),
+                )
+            }
+        };
+        commands.spawn((o.clone(), RigidBody::Static, collider, layers, transform));
+    } );
+}
@@ -1,20 +1,24 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
-use bevy::prelude::*;
+use bevy::{prelude::*, time::common_conditions::on_timer};
 use avian2d::prelude::*;
 use rand::Rng;
 
 use rusty_neat::{ActFunc, NeatContinous, NN};
-use crate::{angle_between_2d, food::Food, GameLayer, Handlers, Hp, Satiety, Stamina};
+use crate::{body::Body, bullets::Gun, config::SimConfig, death::{Damage, DeathCause}, events::{AttackEvent, BirthEvent, FeedEvent, SpeciationEvent}, environment::Environment, lineage::Lineage, food::{setup_food, Food, FoodKind}, map::Obstacles, terrain::Zone, GameLayer, Hp, Satiety, SimRng, SimSet, Stamina};
 
 pub struct SectariiPlugin;
 impl Plugin for SectariiPlugin {
     fn build(&self, app: &mut App) {
+        let config = app.world().resource::<SimConfig>().sectarii.clone();
 
-        let mut agent = NN::new(5, 2, Some((8, 2)), true, 0.75,
+        let vision = Vision { rays: config.sight_rays.max(1) };
+
+        // rusty_neat seeds itself, see `SimRng`
+        let mut agent = NN::new(vision.inputs() + 1, 3, Some((8, 2)), true, 0.75,
             ActFunc::SigmoidBipolar, &[ActFunc::SigmoidBipolar, ActFunc::SELU, ActFunc::Identity, ActFunc::Sinusoid, ActFunc::BinaryStep] );
         agent.set_chances(&[200, 24, 12, 16, 4, 0, 0, 12]);
-        let mut neat = NeatContinous::new(&agent, 2000, 8);
+        let mut neat = NeatContinous::new(&agent, config.population, config.species);
         for _ in 0..20 {
             neat.speciate();
             for _ in 0..10 { neat.species_threshold_correct(); }
@@ -22,14 +26,16 @@ impl Plugin for SectariiPlugin {
 
         app
             .insert_resource(Neat(neat))
+            .insert_resource(vision)
             .insert_resource(UpdateTimer(Timer::from_seconds(60.0, TimerMode::Repeating)))
             .insert_resource(StartupProcedure::default())
-            .add_systems(Startup, setup_sectarii)
-            .add_systems(FixedUpdate, update_system)
-            .add_systems(FixedUpdate, sensor_sight)
-            .add_systems(FixedUpdate, sensor_steal)
-            .add_systems(FixedUpdate, reproduction_system)
-            .add_systems(FixedPostUpdate, evaluate_neat)
+            .insert_resource(SpeciesHues::default())
+            .add_systems(Startup, setup_sectarii.after(setup_food))
+            .add_systems(FixedUpdate, (update_system, sensor_sight).in_set(SimSet::Senses))
+            .add_systems(FixedUpdate, sensor_steal.in_set(SimSet::Interactions))
+            .add_systems(FixedUpdate, reproduction_system.in_set(SimSet::Reproduction))
+            .add_systems(FixedUpdate, cleanup_hues.run_if(on_timer(Duration::from_secs(60))))
+            .add_systems(FixedPostUpdate, evaluate_neat.before(PhysicsSet::Prepare))
         ;
     }
 }
@@ -39,11 +45,12 @@ pub struct Brain {
     pub key: usize,
     pub fitness: (f32, f32),
     pub linvel: f32,
-    pub angvel: f32
+    pub angvel: f32,
+    pub fire: f32,
 }
 impl Brain {
     pub fn new(key: usize) -> Self {
-        Self {key, fitness: (0., 0.), linvel: 0., angvel: 0.}
+        Self {key, fitness: (0., 0.), linvel: 0., angvel: 0., fire: 0.}
     } 
 }
 
@@ -53,31 +60,64 @@ pub struct Species(pub usize);
 #[derive(Component, Clone)]
 pub struct Sectarian;
 
-#[derive(Component, Clone)]
-struct SensorSight {
-    food: (f32, f32),
-    sectarian: (f32, f32),
+/// Ray count of the sight sensor, fixed for the whole run since it sets the network input count.
+/// Range and field of view come from each [`Body`].
+#[derive(Resource, Clone)]
+pub struct Vision {
+    pub rays: usize,
 }
-impl Default for SensorSight {
-    fn default() -> Self {
-        Self { food: (0.,0.), sectarian: (0.,0.) }
+impl Vision {
+    pub fn inputs(&self) -> usize {
+        self.rays * 2
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SightHit {
+    Nothing,
+    Food(FoodKind),
+    Kin,
+    Stranger,
+    Wall,
+}
+impl SightHit {
+    pub fn value(&self) -> f32 {
+        match self {
+            SightHit::Nothing => 0.,
+            SightHit::Food(FoodKind::Grass) => 1.,
+            SightHit::Food(FoodKind::Fruit) => 0.85,
+            SightHit::Food(FoodKind::Carcass) => 0.7,
+            SightHit::Food(FoodKind::Poison) => 0.25,
+            SightHit::Kin => 0.5,
+            SightHit::Stranger => -0.5,
+            SightHit::Wall => -1.,
+        }
+    }
+}
+
+/// Per ray, from the left edge of the field of view: `(1 - distance / range, hit)`.
+#[derive(Component, Clone, Default)]
+pub struct SensorSight {
+    pub rays: Vec<(f32, SightHit)>,
+}
+
 #[derive(Component, Clone)]
-struct SensorSteal;
+pub struct SensorSteal;
 
 #[derive(Resource)]
 pub struct Neat(pub NeatContinous);
-// handle memory leak caused by not removing dead species handles
+
+/// Hue in degrees each species was founded with, drifted from its parent species so that relatives look alike.
+#[derive(Resource, Clone, Default)]
+pub struct SpeciesHues(pub HashMap<usize, f32>);
 
 #[derive(Resource)]
-struct UpdateTimer(Timer);
+pub struct UpdateTimer(pub Timer);
 #[derive(Resource)]
-struct StartupProcedure{
-    p0: Timer,
-    p1: Timer,
-    p2: Timer,
+pub struct StartupProcedure{
+    pub p0: Timer,
+    pub p1: Timer,
+    pub p2: Timer,
 }
 
 impl Default for StartupProcedure {
@@ -91,26 +131,14 @@ impl Default for StartupProcedure {
 }
 fn update_system(
     time: Res<Time>, 
+    vision: Res<Vision>,
     mut timer: ResMut<UpdateTimer>, 
     mut timer_startup: ResMut<StartupProcedure>, 
     mut neat: ResMut<Neat>,
-    mut handlers: ResMut<Handlers>, 
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
 ){
     //let _span = info_span!("updater", name = "updater").entered();
     
     if timer.0.tick(time.delta()).just_finished() {
-        let mut tbd = vec![];
-        handlers.material_sectarii.iter()
-            .filter(|(k, _)| neat.0.species_table.get(k).is_none() ).for_each(|(k,_)| tbd.push(*k) );
-        tbd.iter().for_each(|k| {
-            let h = handlers.material_sectarii.remove(k).unwrap();
-            materials.remove_untracked(&h);
-            let h = handlers.mesh_sectarii.remove(k).unwrap();
-            meshes.remove_untracked(&h);
-        } );
-
         if timer_startup.p2.finished() {
             if neat.0.agents.iter().next().unwrap().1.get_pruning().0 {
                 neat.0.set_pruning(false, 0.2);
@@ -125,20 +153,18 @@ fn update_system(
         }
     }
 
+    // sight, stamina and bias are there from the start
+    let base = vision.inputs() + 2;
     if timer_startup.p0.tick(time.delta()).just_finished() {
         neat.0.agents.values_mut().for_each(|a| {
-            if a.size.0 == 6 {a.add_input();}
-            if a.size.0 == 7 {a.add_input();}
+            while a.size.0 < base + 2 {a.add_input();}
             a.sort_layers(); a.free_nodes_calc();
         } );
         timer_startup.p2.reset();
     }
     if timer_startup.p1.tick(time.delta()).just_finished() {
         neat.0.agents.values_mut().for_each(|a| {
-            if a.size.0 == 8 {a.add_input();}
-            if a.size.0 == 9 {a.add_input();}
-            if a.size.0 == 10 {a.add_input();}
-            if a.size.0 == 11 {a.add_input();}
+            while a.size.0 < base + 9 {a.add_input();}
             a.sort_layers(); a.free_nodes_calc();
         } );
         timer_startup.p2.reset();
@@ -149,105 +175,143 @@ fn update_system(
 fn reproduction_system(
     mut commands: Commands,
     mut neat: ResMut<Neat>,
-    mut handlers: ResMut<Handlers>, 
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut entities: Query<(&mut Satiety, &Brain, &Transform), With<Sectarian>>
+    config: Res<SimConfig>,
+    mut rng: ResMut<SimRng>,
+    mut hues: ResMut<SpeciesHues>,
+    mut lineage: ResMut<Lineage>,
+    mut births: EventWriter<BirthEvent>,
+    mut speciations: EventWriter<SpeciationEvent>,
+    mut entities: Query<(Entity, &mut Satiety, &Brain, &Species, &Body, &Transform), With<Sectarian>>
 ){
     //let _span = info_span!("reproduction", name = "reproduction").entered();
-    entities.iter_mut().for_each(|(mut satiety, brain, transform)| {
-        if satiety.0 > 1.75 {
-            let mut rng = rand::rng();
-            satiety.0 -= 1.0; // Should be > 1.
+    let c = &config.sectarii;
+    let rng = &mut rng.0;
+    entities.iter_mut().for_each(|(parent, mut satiety, brain, species, body, transform)| {
+        if satiety.0 > c.reproduction_threshold {
+            satiety.0 -= c.reproduction_cost; // Should be > 1.
 
             let key = neat.0.offspring(&brain.key);
             let s = neat.0.species_assign(&key);
-            handlers.mesh_sectarii.entry(s)
-                .or_insert( meshes.add(Triangle2d::new(Vec2::Y * -8_f32, Vec2::X * 2_f32, Vec2::X * -2_f32)) );
-            handlers.material_sectarii.entry(s)
-                .or_insert( materials.add(Color::hsv( rng.random_range(0_f32..360_f32), 1_f32, 1_f32) ) );
+            if !lineage.species.contains_key(&s) {
+                speciations.send(SpeciationEvent { species: s, parent_species: species.0, key });
+            }
+            lineage.birth(key, Some(brain.key), s);
 
             let mut transform = transform.clone();
             let dir = transform.local_x().normalize_or_zero();
             transform.translation += 
-                Vec3::new(rng.random_range(-1_f32..=1_f32), rng.random_range(-1_f32..0_f32), 0.).normalize_or_zero() * dir * 128.;
-            spawn_sectarian(&mut commands, &handlers, key, s, transform);
+                Vec3::new(rng.random_range(-1_f32..=1_f32), rng.random_range(-1_f32..0_f32), 0.).normalize_or_zero() * dir * c.offspring_distance;
+            transform.translation = config.world.clamp(transform.translation.truncate()).extend(transform.translation.z);
+            let mut body = body.mutate(&config.body, rng);
+            // a new species drifts away from its parent species, members stay within `hue_drift` of their species color
+            let parent = hues.0.get(&species.0).copied().unwrap_or(body.hue);
+            if s != species.0 {
+                let drift = config.body.speciation_drift;
+                body.hue = *hues.0.entry(s).or_insert_with(|| (parent + rng.random_range(-drift..=drift)).rem_euclid(360.) );
+            } else {
+                let limit = config.body.hue_drift.abs();
+                let offset = ((body.hue - parent + 180.).rem_euclid(360.) - 180.).clamp(-limit, limit);
+                body.hue = (parent + offset).rem_euclid(360.);
+            }
+            let e = spawn_sectarian(&mut commands, rng, key, s, body, transform);
+            births.send(BirthEvent { entity: e, key, species: s, parent: Some((parent, brain.key)), position: transform.translation.truncate() });
         }
     } );
 }
 
 fn sensor_steal(
     time: Res<Time>, 
-    mut neat: ResMut<Neat>,
+    config: Res<SimConfig>,
     col_entities: Query<(&CollidingEntities, &Parent), With<SensorSteal>>,
-    mut sectarii: Query<(&mut Hp, &mut Satiety, &mut Brain), (With<Sectarian>, Without<Food>)>,
-    mut foods: Query<&mut Hp, With<Food>>,
+    mut sectarii: Query<(&mut Hp, &mut Satiety, &Brain, &Species, &mut Damage), (With<Sectarian>, Without<Food>)>,
+    mut foods: Query<(&mut Hp, &FoodKind), With<Food>>,
+    mut feeds: EventWriter<FeedEvent>,
+    mut attacks: EventWriter<AttackEvent>,
 ){
     //let _span = info_span!("sensor_steal", name = "sensor_steal").entered();
+    let eat = time.delta_secs() * config.food.eat_rate;
+    let c = &config.sectarii;
+    let drain = time.delta_secs() * c.predation_rate;
     col_entities.iter().for_each(|(ce, parent_key)| {
         let parent_entity = parent_key.get();
 
         ce.iter().for_each(|e| {
-            if let Ok(mut hp) = foods.get_mut(*e) {
+            if let Ok((mut hp, kind)) = foods.get_mut(*e) {
+                let k = config.food.kind(*kind);
                 let mut sectarian = sectarii.get_mut(parent_entity).unwrap();
-                hp.0 -= time.delta_secs();
-                sectarian.1.0 += time.delta_secs();
-                sectarian.2.fitness.0 += time.delta_secs()/2.;
-                neat.0.agents.get_mut(&sectarian.2.key).unwrap().fitness += time.delta_secs();
+                hp.0 -= eat;
+                sectarian.1.0 += eat * k.nutrition;
+                sectarian.0.0 -= eat * k.toxic;
+                if k.toxic > 0. { sectarian.4.add(DeathCause::Poison, eat * k.toxic); }
+                feeds.send(FeedEvent { entity: parent_entity, key: sectarian.2.key, food: *e, kind: *kind, hp: eat, satiety: eat * k.nutrition });
             }
             if let Ok([mut enemy, mut parent]) = sectarii.get_many_mut([*e, parent_entity ]) {
-                enemy.0.0 -= time.delta_secs();
-                parent.1.0 += time.delta_secs() * 0.3;
-                parent.0.0 += time.delta_secs() * 0.7;
-                parent.2.fitness.1 += time.delta_secs()/2.;
-                neat.0.agents.get_mut(&parent.2.key).unwrap().fitness += time.delta_secs()/2.;
+                if !c.same_species_predation && enemy.3.0 == parent.3.0 { return }
+                enemy.0.0 -= drain;
+                enemy.4.attack(DeathCause::Predation, parent_entity, parent.2.key, drain);
+                parent.1.0 += drain * c.predation_satiety;
+                parent.0.0 += drain * c.predation_hp;
+                attacks.send(AttackEvent {
+                    attacker: parent_entity, attacker_key: parent.2.key, target: *e, target_key: enemy.2.key,
+                    cause: DeathCause::Predation, hp: drain,
+                });
             }
         });
     });
 }
 
 fn sensor_sight(
-    mut col_sectarii: Query<(&CollidingEntities, &Parent, &mut SensorSight, &GlobalTransform)>,
-    sectarii: Query<&Transform, With<Sectarian>>,
-    foods: Query<&Transform, With<Food>>,
+    config: Res<SimConfig>,
+    vision: Res<Vision>,
+    environment: Res<Environment>,
+    spatial: SpatialQuery,
+    mut sectarii: Query<(Entity, &Transform, &Species, &Body, &mut SensorSight), With<Sectarian>>,
+    others: Query<&Species, With<Sectarian>>,
+    foods: Query<&FoodKind, With<Food>>,
+    sensors: Query<(), With<Sensor>>,
 ){
     //let _span = info_span!("sensor_sight", name = "sensor_sight").entered();
-    col_sectarii.par_iter_mut().for_each(|(ce, parent, mut sensor, transform)| {
-        match 
-            ce.iter().filter(|e| foods.get(**e).is_ok() ).map(|e| {
-                let food = foods.get(*e).unwrap();
-                ((food.translation - transform.translation()).length(), *e )
-            } ).min_by(|a, b| a.0.partial_cmp(&b.0).unwrap() )
-        {
-            None => sensor.food = (0.,0.),
-            Some((d, e)) => {
-                let food = foods.get(e).unwrap();
-                let angle = angle_between_2d(&transform.compute_transform(), food);
-                sensor.food = (1. - (d / 180.), angle);
+    let light = environment.sight(&config.environment);
+    let predicate = |e: Entity| !sensors.contains(e);
+
+    sectarii.par_iter_mut().for_each(|(e, transform, species, body, mut sensor)| {
+        let range = body.sight_range * light;
+        let step = if vision.rays > 1 { body.sight_fov / (vision.rays - 1) as f32 } else { 0. };
+        let origin = transform.translation.truncate();
+        let forward = transform.local_y().truncate();
+        let filter = SpatialQueryFilter::from_mask([GameLayer::Default, GameLayer::Foods, GameLayer::Sectarii])
+            .with_excluded_entities([e]);
+
+        sensor.rays = (0..vision.rays).map(|i| {
+            let angle = if vision.rays > 1 { -body.sight_fov / 2. + step * i as f32 } else { 0. };
+            let dir = Dir2::new(Vec2::from_angle(angle).rotate(forward)).unwrap_or(Dir2::Y);
+            match spatial.cast_ray_predicate(origin, dir, range, true, &filter, &predicate) {
+                None => (0., SightHit::Nothing),
+                Some(hit) => {
+                    let kind = if let Ok(food) = foods.get(hit.entity) { SightHit::Food(*food) }
+                        else if let Ok(s) = others.get(hit.entity) {
+                            if s.0 == species.0 { SightHit::Kin } else { SightHit::Stranger }
+                        }
+                        else { SightHit::Wall };
+                    (1. - hit.distance / range, kind)
+                }
             }
-        }
-
-        match 
-            ce.iter().filter(|e| **e != parent.get() && sectarii.get(**e).is_ok() ).map(|e| {
-                let enemy = sectarii.get(*e).unwrap();
-                ((enemy.translation - transform.translation()).length(), *e )
-            } ).min_by(|a, b| a.0.partial_cmp(&b.0).unwrap() )
-        {
-            None => sensor.sectarian = (0.,0.),
-            Some((d, e)) => {
-                let enemy = sectarii.get(e).unwrap();
-                let angle = angle_between_2d(&transform.compute_transform(), enemy);
-                sensor.sectarian = (1. - (d / 180.), angle);
-            }
-        }
+        } ).collect();
     });
 }
 
+fn cleanup_hues(
+    neat: Res<Neat>,
+    mut hues: ResMut<SpeciesHues>,
+){
+    hues.0.retain(|s, _| neat.0.species_table.get(s).is_some() );
+}
+
 #[derive(Component, Clone)]
-struct BioClock {timer_short: Timer, timer_long: Timer, state_short: isize, state_long: isize}
+pub struct BioClock {pub timer_short: Timer, pub timer_long: Timer, pub state_short: isize, pub state_long: isize}
 
 impl BioClock {
-    fn new(timer_s: f32, timer_l: f32) -> Self {
+    pub fn new(timer_s: f32, timer_l: f32) -> Self {
         Self { 
             timer_short: Timer::from_seconds(timer_s, TimerMode::Repeating), 
             timer_long: Timer::from_seconds(timer_l, TimerMode::Repeating), 
@@ -255,7 +319,7 @@ impl BioClock {
             state_long: 1 
         }
     }
-    fn tick(&mut self, delta: Duration){
+    pub fn tick(&mut self, delta: Duration){
         if self.timer_short.tick(delta).just_finished() {
             self.state_short = match self.state_short {
                 -1 => 0,
@@ -279,59 +343,67 @@ impl BioClock {
 fn evaluate_neat(
     mut neat: ResMut<Neat>,
     time: Res<Time>, 
+    config: Res<SimConfig>,
+    vision: Res<Vision>,
+    environment: Res<Environment>,
     //graph: Res<Graph>,
-    sight: Query<(&Parent, &SensorSight)>,
     mut sectarii: Query<(
         &mut Brain, 
         &mut ExternalForce, &mut ExternalTorque, &Transform, 
         &LinearVelocity, &AngularVelocity, &Hp, &Satiety, &Stamina,
-        &mut BioClock
+        &mut BioClock, &SensorSight, &Zone
     ), With<Sectarian>>,
 ) {
     //let _span = info_span!("eval_neat", name = "eval_neat").entered();
-    let inputs = sight.iter().map(|(parent_key, sensor)|{
-        let parent = sectarii.get(parent_key.get()).unwrap();
-        let ins = vec![ 
-            sensor.food.0, sensor.food.1, sensor.sectarian.0, sensor.sectarian.1,
+    let daylight = environment.daylight(&config.environment);
+    let season = environment.season(&config.environment);
+    let inputs = sectarii.iter().map(|parent|{
+        // freshly spawned sectarii haven't looked around yet
+        let mut ins = (0..vision.rays).flat_map(|i| {
+            let (d, kind) = parent.10.rays.get(i).copied().unwrap_or((0., SightHit::Nothing));
+            [d, kind.value()]
+        } ).collect::<Vec<_>>();
+        ins.extend([
             parent.8.0, 
             parent.9.state_short as f32, parent.9.state_long as f32,
             parent.6.0, parent.7.0,
             parent.0.linvel, parent.0.angvel, 
-        ];
+            parent.11.0.value(),
+            daylight, season,
+        ]);
         (parent.0.key, ins)
     }).collect();
 
     neat.0.check_integrity(&inputs);
     neat.0.forward(&inputs);
 
-    sectarii.par_iter_mut().for_each(|(mut brain, mut force, mut torque, transform, lv, av, _, _, _, mut clock)|{
+    sectarii.par_iter_mut().for_each(|(mut brain, mut force, mut torque, transform, lv, av, _, _, _, mut clock, _, _)|{
         clock.tick(time.delta());
         let o = neat.0.get_outputs(&brain.key);
-        force.apply_force(transform.local_y().truncate().normalize_or_zero() * o[0] * 3.);
-        torque.apply_torque(o[1]* 4.);
+        force.apply_force(transform.local_y().truncate().normalize_or_zero() * o[0] * config.sectarii.force);
+        torque.apply_torque(o[1]* config.sectarii.torque);
+        brain.fire = o.get(2).copied().unwrap_or(0.);
         brain.linvel = lv.length();
         brain.angvel = av.0;
     });
 }
 
 
-fn spawn_sectarian(commands: &mut Commands, handlers: &ResMut<Handlers>, key: usize, species: usize, transform: Transform){
-    let mut rng = rand::rng();
+pub fn spawn_sectarian(commands: &mut Commands, rng: &mut impl Rng, key: usize, species: usize, body: Body, transform: Transform) -> Entity {
     let mut e = commands.spawn(Sectarian);
     e.insert(Brain::new(key));
     e.insert(Hp(1_f32));
     e.insert(Stamina(1_f32));
     e.insert(Satiety(1_f32));
+    e.insert(Gun::default());
     e.insert(Species( species ));
     e.insert(BioClock::new(rng.random_range(0.4..0.6), rng.random_range(3.9..4.1)));
-    e.insert(transform);
-    e.insert(Mesh2d( handlers.mesh_sectarii.get(&species).unwrap().clone() ));
-    e.insert(MeshMaterial2d( handlers.material_sectarii.get(&species).unwrap().clone() ));
+    e.insert(transform.with_scale(Vec3::splat(body.size)));
     e.insert(RigidBody::Dynamic);
     e.insert(Collider::triangle_unchecked(Vec2::Y * -10_f32, Vec2::X * 2.5_f32, Vec2::X * -2.5_f32));
     e.insert(CollisionLayers::new([GameLayer::Default, GameLayer::Sectarii], 
-        [GameLayer::Default, GameLayer::Sectarii, GameLayer::Foods, GameLayer::Bullets]));
-    e.insert(ColliderDensity(0.001));
+        [GameLayer::Default, GameLayer::Sectarii, GameLayer::Foods, GameLayer::Bullets, GameLayer::Walls, GameLayer::Obstacles]));
+    e.insert(ColliderDensity(0.001 * body.density));
     e.insert(Friction::new(0.4));
     e.insert(LinearDamping(2.0));
     e.insert(AngularDamping(1.0));
@@ -339,54 +411,55 @@ fn spawn_sectarian(commands: &mut Commands, handlers: &ResMut<Handlers>, key: us
     e.insert(ExternalTorque::ZERO.with_persistence(false));
     e.insert(LinearVelocity::ZERO);
     e.insert(AngularVelocity::ZERO);
-
-    e.with_child(( 
-        SensorSight::default(),
-        Collider::compound(vec![
-            (
-                Position::default(), Rotation::default(),
-                Collider::triangle_unchecked(Vec2::new(0., 0.), Vec2::new(1.5, 3.) * 40., Vec2::new(-1.5, 3.) * 40.), 
-            ),
-            (
-                Position::default(), Rotation::default(),
-                Collider::circle(32.), 
-            )
-        ]),
-        Sensor,
-        CollisionLayers::new([GameLayer::Foods, GameLayer::Sectarii], [GameLayer::Foods, GameLayer::Sectarii]),
-        CollidingEntities::default(),
-        GlobalTransform::default()
-    ));
+    e.insert(SensorSight::default());
+    e.insert(Zone::default());
+    e.insert(Damage::default());
+    // the sensor inherits the body scale
+    let steal = body.steal_radius / body.size;
+    e.insert(body);
 
     e.with_child(( 
         SensorSteal,
-        Collider::circle(2.5_f32),
+        Collider::circle(steal),
         Sensor,
         CollisionLayers::new([GameLayer::Foods, GameLayer::Sectarii], [GameLayer::Foods, GameLayer::Sectarii]),
         CollidingEntities::default(),
     ));
+
+    e.id()
 }
 
 fn setup_sectarii(
     mut commands: Commands,
     mut neat: ResMut<Neat>,
-    mut handlers: ResMut<Handlers>, 
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<SimConfig>,
+    obstacles: Res<Obstacles>,
+    mut rng: ResMut<SimRng>,
+    mut hues: ResMut<SpeciesHues>,
+    mut lineage: ResMut<Lineage>,
+    mut births: EventWriter<BirthEvent>,
 ) {
-    let mut rng = rand::rng();
+    let r = config.sectarii.spawn_range;
+    let rng = &mut rng.0;
     neat.0.speciate();
     neat.0.speciate();
     neat.0.speciate();
     neat.0.speciate();
     
-    neat.0.species_table.keys().for_each(|k| {
-        handlers.mesh_sectarii.insert(*k, meshes.add(Triangle2d::new(Vec2::Y * -8_f32, Vec2::X * 2_f32, Vec2::X * -2_f32)) );
-        handlers.material_sectarii.insert(*k, materials.add(Color::hsv( rng.random_range(0_f32..360_f32), 1_f32, 1_f32)) );
-    } );
-    
-    neat.0.agents.iter().for_each(|(k, a)| {
-        spawn_sectarian(&mut commands, &handlers, *k, a.species,
-            Transform::from_xyz(rng.random_range(-6_000_f32..6_000_f32), rng.random_range(-6_000_f32..6_000_f32), 0.));
+    let mut agents = neat.0.agents.iter().map(|(k, a)| (*k, a.species) ).collect::<Vec<_>>();
+    agents.sort_unstable();
+    // the first species are spread evenly around the color wheel
+    let mut species = agents.iter().map(|a| a.1 ).collect::<Vec<_>>();
+    species.sort_unstable();
+    species.dedup();
+    let offset = rng.random_range(0_f32..360.);
+    hues.0 = species.iter().enumerate()
+        .map(|(i, s)| (*s, (offset + i as f32 * 360. / species.len() as f32).rem_euclid(360.)) ).collect();
+    agents.into_iter().for_each(|(k, species)| {
+        let transform = Transform::from_translation(obstacles.sample(&config.world, rng, r).extend(0.));
+        let hue = hues.0[&species];
+        lineage.birth(k, None, species);
+        let e = spawn_sectarian(&mut commands, rng, k, species, Body::new(&config.sectarii, hue), transform);
+        births.send(BirthEvent { entity: e, key: k, species, parent: None, position: transform.translation.truncate() });
     });
 }
@@ -0,0 +1,290 @@
+use std::{collections::HashMap, fs, io::{BufReader, BufWriter}, path::{Path, PathBuf}, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use avian2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use rand_chacha::ChaCha12Rng;
+
+use rusty_neat::NeatContinous;
+use crate::{
+    body::Body,
+    bullets::{Bullet, Gun},
+    config::SimConfig,
+    death::{Damage, DeathCause, DeathStats},
+    environment::Environment,
+    lineage::Lineage,
+    food::{spawn_carcass, spawn_food, Carcass, Food, FoodKind, Plant},
+    sectarii::{spawn_sectarian, BioClock, Brain, Neat, Sectarian, Species, SpeciesHues, StartupProcedure, UpdateTimer},
+    Hp, Satiety, SimRng, Stamina, WorldSeed
+};
+
+pub struct SnapshotPlugin {
+    /// snapshot restored right after startup
+    pub load: Option<PathBuf>,
+}
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        let autosave = app.world().resource::<SimConfig>().snapshot.autosave;
+
+        app
+            .add_event::<SaveSnapshot>()
+            .add_event::<LoadSnapshot>()
+            .add_event::<SnapshotLoaded>()
+            .add_systems(PreUpdate, (save_snapshot, load_snapshot).chain())
+        ;
+        if let Some(p) = self.load.clone() {
+            app.add_systems(PostStartup, move |mut events: EventWriter<LoadSnapshot>| { events.send(LoadSnapshot(p.clone())); });
+        }
+        if autosave > 0. {
+            app.add_systems(Update, autosave_system.run_if(on_timer(Duration::from_secs_f32(autosave))));
+        }
+    }
+}
+
+/// Writes the whole world to the given file.
+#[derive(Event)]
+pub struct SaveSnapshot(pub PathBuf);
+/// Replaces the whole world with the one stored in the given file.
+#[derive(Event)]
+pub struct LoadSnapshot(pub PathBuf);
+/// A [`LoadSnapshot`] replaced the world, the despawned entities are gone by `PostUpdate`.
+#[derive(Event)]
+pub struct SnapshotLoaded(pub PathBuf);
+
+#[derive(Serialize, Deserialize)]
+struct WorldSnapshot<N, L> {
+    neat: N,
+    update_timer: TimerState,
+    startup: [TimerState; 3],
+    #[serde(default)]
+    environment: f32,
+    #[serde(default)]
+    species_hues: HashMap<usize, f32>,
+    #[serde(default)]
+    lineage: L,
+    /// state of the [`SimRng`]
+    #[serde(default)]
+    rng: Option<ChaCha12Rng>,
+    /// [`WorldSeed`] the map was generated from
+    #[serde(default)]
+    world_seed: Option<u64>,
+    #[serde(default)]
+    deaths: DeathStats,
+    sectarii: Vec<SectarianState>,
+    foods: Vec<FoodState>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SectarianState {
+    key: usize,
+    species: usize,
+    fitness: (f32, f32),
+    position: [f32; 3],
+    rotation: f32,
+    linvel: [f32; 2],
+    angvel: f32,
+    hp: f32,
+    satiety: f32,
+    stamina: f32,
+    clock: (TimerState, TimerState, isize, isize),
+    #[serde(default)]
+    body: Option<Body>,
+    /// seconds until the next shot
+    #[serde(default)]
+    gun: f32,
+    /// hp lost by [`DeathCause`] and the network keys of the attackers with the hp they took
+    #[serde(default)]
+    damage: ([f32; DeathCause::ALL.len()], Vec<(usize, f32)>),
+}
+
+#[derive(Serialize, Deserialize)]
+struct FoodState {
+    position: [f32; 3],
+    hp: f32,
+    #[serde(default)]
+    kind: FoodKind,
+    /// seconds left for carcasses
+    #[serde(default)]
+    carcass: Option<f32>,
+    /// seconds until plants drop their next seed
+    #[serde(default)]
+    seed: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct TimerState {
+    duration: f32,
+    elapsed: f32,
+}
+impl TimerState {
+    fn of(timer: &Timer) -> Self {
+        Self { duration: timer.duration().as_secs_f32(), elapsed: timer.elapsed_secs() }
+    }
+    fn restore(&self, timer: &mut Timer) {
+        // set_elapsed keeps the finished flag of a once-timer that already fired
+        timer.reset();
+        timer.set_duration(Duration::from_secs_f32(self.duration));
+        timer.set_elapsed(Duration::from_secs_f32(self.elapsed));
+        // refresh the finished flag without letting once-timers fire again
+        timer.tick(Duration::ZERO);
+    }
+}
+
+fn read(path: &Path) -> Result<WorldSnapshot<NeatContinous, Lineage>, String> {
+    let f = fs::File::open(path).map_err(|e| e.to_string())?;
+    ron::de::from_reader(BufReader::new(f)).map_err(|e| e.to_string())
+}
+
+/// Seed the world of a snapshot was generated from, read before building the world to resume in.
+pub fn world_seed(path: &Path) -> Option<u64> {
+    read(path).inspect_err(|e| error!("snapshot {:?} not read: {}", path, e)).ok()?.world_seed
+}
+
+fn autosave_system(
+    config: Res<SimConfig>,
+    mut events: EventWriter<SaveSnapshot>,
+){
+    events.send(SaveSnapshot(config.snapshot.path.clone()));
+}
+
+fn save_snapshot(
+    mut events: EventReader<SaveSnapshot>,
+    neat: Res<Neat>,
+    timer: Res<UpdateTimer>,
+    timer_startup: Res<StartupProcedure>,
+    environment: Res<Environment>,
+    hues: Res<SpeciesHues>,
+    lineage: Res<Lineage>,
+    deaths: Res<DeathStats>,
+    rng: Res<SimRng>,
+    world_seed: Res<WorldSeed>,
+    sectarii: Query<(
+        &Brain, &Species, &Transform, &LinearVelocity, &AngularVelocity,
+        &Hp, &Satiety, &Stamina, &BioClock, &Body, &Gun, &Damage
+    ), With<Sectarian>>,
+    foods: Query<(&Transform, &Hp, &FoodKind, Option<&Carcass>, Option<&Plant>), With<Food>>,
+){
+    let Some(SaveSnapshot(path)) = events.read().last() else { return };
+
+    let snapshot = WorldSnapshot {
+        neat: &neat.0,
+        update_timer: TimerState::of(&timer.0),
+        startup: [TimerState::of(&timer_startup.p0), TimerState::of(&timer_startup.p1), TimerState::of(&timer_startup.p2)],
+        environment: environment.elapsed,
+        species_hues: hues.0.clone(),
+        lineage: &*lineage,
+        rng: Some(rng.0.clone()),
+        world_seed: Some(world_seed.0),
+        deaths: deaths.clone(),
+        sectarii: sectarii.iter().map(|(brain, species, t, lv, av, hp, satiety, stamina, clock, body, gun, damage)| SectarianState {
+            key: brain.key,
+            species: species.0,
+            fitness: brain.fitness,
+            position: t.translation.to_array(),
+            rotation: t.rotation.to_euler(EulerRot::XYZ).2,
+            linvel: lv.0.to_array(),
+            angvel: av.0,
+            hp: hp.0,
+            satiety: satiety.0,
+            stamina: stamina.0,
+            clock: (TimerState::of(&clock.timer_short), TimerState::of(&clock.timer_long), clock.state_short, clock.state_long),
+            body: Some(body.clone()),
+            gun: gun.0,
+            damage: (damage.taken, damage.attackers.iter().map(|a| (a.1, a.2) ).collect()),
+        } ).collect(),
+        foods: foods.iter().map(|(t, hp, kind, carcass, plant)| FoodState {
+            position: t.translation.to_array(), hp: hp.0, kind: *kind, carcass: carcass.map(|c| c.0), seed: plant.map(|p| p.0),
+        } ).collect(),
+    };
+
+    // write next to the target first so a crash mid-write keeps the previous snapshot intact
+    let tmp = path.with_extension("tmp");
+    let result = fs::File::create(&tmp).map_err(|e| e.to_string())
+        .and_then(|f| ron::ser::to_writer(BufWriter::new(f), &snapshot).map_err(|e| e.to_string()))
+        .and_then(|_| fs::rename(&tmp, path).map_err(|e| e.to_string()));
+    match result {
+        Ok(_) => info!("snapshot saved to {:?}", path),
+        Err(e) => error!("snapshot {:?} not saved: {}", path, e),
+    }
+}
+
+fn load_snapshot(
+    mut commands: Commands,
+    mut events: EventReader<LoadSnapshot>,
+    mut loaded: EventWriter<SnapshotLoaded>,
+    config: Res<SimConfig>,
+    mut neat: ResMut<Neat>,
+    mut timer: ResMut<UpdateTimer>,
+    mut timer_startup: ResMut<StartupProcedure>,
+    mut environment: ResMut<Environment>,
+    mut hues: ResMut<SpeciesHues>,
+    mut lineage: ResMut<Lineage>,
+    mut deaths: ResMut<DeathStats>,
+    mut rng: ResMut<SimRng>,
+    world_seed: Res<WorldSeed>,
+    entities: Query<Entity, Or<(With<Sectarian>, With<Food>, With<Bullet>)>>,
+){
+    let Some(LoadSnapshot(path)) = events.read().last() else { return };
+
+    let snapshot = match read(path) {
+        Ok(s) => s,
+        Err(e) => { error!("snapshot {:?} not loaded: {}", path, e); return }
+    };
+    if snapshot.world_seed.is_some_and(|s| s != world_seed.0) {
+        warn!("snapshot {:?} comes from another world, start with `--snapshot` to restore its map", path);
+    }
+
+    entities.iter().for_each(|e| commands.entity(e).despawn_recursive() );
+
+    neat.0 = snapshot.neat;
+    snapshot.update_timer.restore(&mut timer.0);
+    snapshot.startup[0].restore(&mut timer_startup.p0);
+    snapshot.startup[1].restore(&mut timer_startup.p1);
+    snapshot.startup[2].restore(&mut timer_startup.p2);
+    environment.elapsed = snapshot.environment;
+    hues.0 = snapshot.species_hues;
+    *lineage = snapshot.lineage;
+    *deaths = snapshot.deaths;
+
+    let spawned = snapshot.sectarii.iter().map(|s| {
+        let transform = Transform::from_translation(Vec3::from_array(s.position))
+            .with_rotation(Quat::from_rotation_z(s.rotation));
+        let body = s.body.clone().unwrap_or_else(|| Body::new(&config.sectarii, 0.) );
+        let e = spawn_sectarian(&mut commands, &mut rng.0, s.key, s.species, body, transform);
+        // snapshots from before lineage tracking
+        if !lineage.records.contains_key(&s.key) { lineage.birth(s.key, None, s.species); }
+
+        let mut clock = BioClock::new(s.clock.0.duration, s.clock.1.duration);
+        s.clock.0.restore(&mut clock.timer_short);
+        s.clock.1.restore(&mut clock.timer_long);
+        clock.state_short = s.clock.2;
+        clock.state_long = s.clock.3;
+
+        let linvel = Vec2::from_array(s.linvel);
+        commands.entity(e).insert((
+            Brain { key: s.key, fitness: s.fitness, linvel: linvel.length(), angvel: s.angvel, fire: 0. },
+            Hp(s.hp), Satiety(s.satiety), Stamina(s.stamina),
+            LinearVelocity(linvel), AngularVelocity(s.angvel),
+            clock, Gun(s.gun),
+        ));
+        (s.key, e)
+    } ).collect::<HashMap<_, _>>();
+    // attackers point at the new entities, those that died before the save can't be found anymore
+    snapshot.sectarii.iter().for_each(|s| {
+        let attackers = s.damage.1.iter().map(|(k, hp)| (spawned.get(k).copied().unwrap_or(Entity::PLACEHOLDER), *k, *hp) ).collect();
+        commands.entity(spawned[&s.key]).insert(Damage { taken: s.damage.0, attackers });
+    } );
+    snapshot.foods.iter().for_each(|f| {
+        let transform = Transform::from_translation(Vec3::from_array(f.position));
+        match f.carcass {
+            Some(decay) => spawn_carcass(&mut commands, transform, f.hp, decay),
+            None => spawn_food(&mut commands, transform, f.hp, f.kind, f.seed.unwrap_or(config.food.seed_interval)),
+        }
+    } );
+    // after the spawns above, which draw values the saved run never drew
+    if let Some(state) = snapshot.rng { rng.0 = state; }
+
+    info!("snapshot loaded from {:?}: {} sectarii, {} foods", path, snapshot.sectarii.len(), snapshot.foods.len());
+    loaded.send(SnapshotLoaded(path.clone()));
+}
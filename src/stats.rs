@@ -0,0 +1,170 @@
+use std::{collections::VecDeque, fs::{self, File, OpenOptions}, io::Write, path::Path, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{config::SimConfig, death::DeathCause, events::{BirthEvent, DeathEvent}, food::Food, lineage::Lineage, sectarii::{Brain, Neat, Sectarian}, snapshot::SnapshotLoaded, Hp, Satiety, SimSet, Stamina};
+
+pub struct StatsPlugin {
+    /// keep the rows of a previous run, set when resuming from a snapshot
+    pub append: bool,
+}
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        let config = app.world().resource::<SimConfig>().stats.clone();
+        let p = &config.path;
+        let file = (!p.as_os_str().is_empty()).then(|| {
+            if let Some(d) = p.parent() { let _ = fs::create_dir_all(d); }
+            OpenOptions::new().write(true).create(true).append(self.append).truncate(!self.append).open(p)
+                .and_then(|mut f| {
+                    if f.metadata()?.len() == 0 { writeln!(f, "{}", StatsSample::HEADER)?; }
+                    Ok(f)
+                })
+                .inspect_err(|e| error!("stats {:?} not written: {}", p, e)).ok()
+        }).flatten();
+
+        app
+            .insert_resource(Stats {
+                history: VecDeque::new(),
+                births: 0, deaths: 0, causes: [0; DeathCause::ALL.len()],
+                timer: Timer::from_seconds(config.interval, TimerMode::Repeating),
+                file,
+            })
+            .add_systems(PostUpdate, count_system)
+            .add_systems(FixedUpdate, sample_system.after(SimSet::Death))
+        ;
+    }
+}
+
+/// Per-interval metrics of the whole population; counters cover the interval since the previous sample.
+#[derive(Clone, Debug, Default)]
+pub struct StatsSample {
+    /// simulated seconds since the first generation, carried over by snapshots
+    pub time: f32,
+    pub population: usize,
+    pub species: usize,
+    pub species_threshold: f32,
+    pub foods: usize,
+    pub births: usize,
+    pub deaths: usize,
+    /// deaths in the interval by [`DeathCause`]
+    pub deaths_by_cause: [usize; DeathCause::ALL.len()],
+    pub fitness_food: f32,
+    pub fitness_predation: f32,
+    pub fitness_max: f32,
+    pub hp: f32,
+    pub satiety: f32,
+    pub stamina: f32,
+    /// mean node count of the networks
+    pub genome_nodes: f32,
+    /// mean connection count of the networks
+    pub genome_connections: f32,
+}
+impl StatsSample {
+    const HEADER: &str = "time,population,species,species_threshold,foods,births,deaths,\
+        deaths_starvation,deaths_exhaustion,deaths_predation,deaths_shot,deaths_poison,fitness_food,fitness_predation,fitness_max,hp,satiety,stamina,genome_nodes,genome_connections";
+
+    fn csv_row(&self) -> String {
+        let d = &self.deaths_by_cause;
+        format!("{:.2},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.time, self.population, self.species, self.species_threshold, self.foods, self.births, self.deaths,
+            d[0], d[1], d[2], d[3], d[4], self.fitness_food, self.fitness_predation, self.fitness_max, self.hp, self.satiety, self.stamina,
+            self.genome_nodes, self.genome_connections)
+    }
+}
+
+#[derive(Resource)]
+pub struct Stats {
+    /// most recent samples, oldest first
+    pub history: VecDeque<StatsSample>,
+    births: usize,
+    deaths: usize,
+    causes: [usize; DeathCause::ALL.len()],
+    timer: Timer,
+    file: Option<File>,
+}
+
+fn count_system(
+    config: Res<SimConfig>,
+    lineage: Res<Lineage>,
+    mut stats: ResMut<Stats>,
+    mut loaded: EventReader<SnapshotLoaded>,
+    mut born: EventReader<BirthEvent>,
+    mut dead: EventReader<DeathEvent>,
+    sectarii: Query<(), With<Sectarian>>,
+){
+    if loaded.read().count() > 0 {
+        rewind(&mut stats, &config.stats.path, lineage.elapsed);
+        // skip the births of the replaced world, like the first generation spawned before a `--snapshot` load
+        stats.births += born.read().filter(|e| sectarii.contains(e.entity) ).count();
+    }
+    else { stats.births += born.read().count(); }
+    dead.read().for_each(|e| {
+        stats.deaths += 1;
+        stats.causes[e.cause as usize] += 1;
+    } );
+}
+
+fn sample_system(
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    neat: Res<Neat>,
+    lineage: Res<Lineage>,
+    mut stats: ResMut<Stats>,
+    sectarii: Query<(&Brain, &Hp, &Satiety, &Stamina), With<Sectarian>>,
+    foods: Query<(), With<Food>>,
+){
+    let interval = Duration::from_secs_f32(config.stats.interval);
+    if stats.timer.duration() != interval { stats.timer.set_duration(interval); }
+    if !stats.timer.tick(time.delta()).just_finished() { return }
+
+    let n = sectarii.iter().len().max(1) as f32;
+    let agents = neat.0.agents.len().max(1) as f32;
+    let sample = StatsSample {
+        time: lineage.elapsed,
+        population: sectarii.iter().len(),
+        species: neat.0.species_table.len(),
+        species_threshold: neat.0.species_threshold,
+        foods: foods.iter().len(),
+        births: stats.births,
+        deaths: stats.deaths,
+        deaths_by_cause: stats.causes,
+        fitness_food: sectarii.iter().map(|s| s.0.fitness.0 ).sum::<f32>() / n,
+        fitness_predation: sectarii.iter().map(|s| s.0.fitness.1 ).sum::<f32>() / n,
+        fitness_max: sectarii.iter().map(|s| s.0.fitness.0 + s.0.fitness.1 ).fold(0., f32::max),
+        hp: sectarii.iter().map(|s| s.1.0 ).sum::<f32>() / n,
+        satiety: sectarii.iter().map(|s| s.2.0 ).sum::<f32>() / n,
+        stamina: sectarii.iter().map(|s| s.3.0 ).sum::<f32>() / n,
+        genome_nodes: neat.0.agents.values().map(|a| a.nodes.len() as f32 ).sum::<f32>() / agents,
+        genome_connections: neat.0.agents.values().map(|a| a.connections.len() as f32 ).sum::<f32>() / agents,
+    };
+    stats.births = 0;
+    stats.deaths = 0;
+    stats.causes = [0; DeathCause::ALL.len()];
+
+    if let Some(f) = stats.file.as_mut() {
+        if let Err(e) = writeln!(f, "{}", sample.csv_row()) { error!("stats not written: {}", e); }
+    }
+    stats.history.push_back(sample);
+    while stats.history.len() > config.stats.history { stats.history.pop_front(); }
+}
+
+/// Restarts the counters at the time of a loaded snapshot and drops the samples taken after it.
+fn rewind(stats: &mut Stats, path: &Path, time: f32) {
+    stats.births = 0;
+    stats.deaths = 0;
+    stats.causes = [0; DeathCause::ALL.len()];
+    stats.timer.reset();
+    // rows are written with two decimals
+    let kept = |t: f32| t <= time + 0.01;
+    stats.history.retain(|s| kept(s.time) );
+
+    if stats.file.take().is_none() { return }
+    let rows = fs::read_to_string(path).map(|text| text.lines().skip(1)
+        .filter(|l| l.split(',').next().and_then(|t| t.parse::<f32>().ok()).is_some_and(kept) )
+        .fold(format!("{}\n", StatsSample::HEADER), |acc, l| acc + l + "\n")
+    );
+    stats.file = rows.and_then(|rows| fs::write(path, rows))
+        .and_then(|_| OpenOptions::new().append(true).open(path))
+        .inspect_err(|e| error!("stats {:?} not written: {}", path, e)).ok();
+}
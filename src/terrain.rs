@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use avian2d::prelude::*;
+use noise::{NoiseFn, Perlin};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::SimConfig, sectarii::Sectarian, SimSet, WorldSeed};
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = app.world().resource::<WorldSeed>().rng(2).random();
+        let config = app.world().resource::<SimConfig>().clone();
+        let mut terrain = Terrain { noise: Perlin::new(seed), regrow_mean: 1. };
+        terrain.regrow_mean = terrain.mean_regrow(&config.terrain, config.food.regrow_range);
+
+        app
+            .insert_resource(terrain)
+            .add_systems(FixedUpdate, mean_system.before(SimSet::Senses).run_if(resource_changed::<SimConfig>))
+            .add_systems(FixedUpdate, zone_system.in_set(SimSet::Senses))
+        ;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TerrainKind {
+    #[default]
+    Open,
+    Water,
+    Mud,
+}
+impl TerrainKind {
+    /// Sensor input of the zone the sectarian stands in.
+    pub fn value(&self) -> f32 {
+        match self {
+            TerrainKind::Open => 0.,
+            TerrainKind::Water => -1.,
+            TerrainKind::Mud => 1.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ZoneConfig {
+    pub linear_damping: f32,
+    pub friction: f32,
+    /// multiplier of the stamina spent while moving
+    pub stamina_cost: f32,
+    /// relative chance for regrowing food to land in the zone
+    pub regrow: f32,
+}
+impl Default for ZoneConfig {
+    fn default() -> Self {
+        Self { linear_damping: 2., friction: 0.4, stamina_cost: 1., regrow: 1. }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TerrainConfig {
+    /// everything is open ground when disabled
+    pub enabled: bool,
+    /// world units per noise period
+    pub scale: f32,
+    /// noise below this level is water
+    pub water_level: f32,
+    /// noise above this level is mud
+    pub mud_level: f32,
+    pub open: ZoneConfig,
+    pub water: ZoneConfig,
+    pub mud: ZoneConfig,
+}
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false, scale: 3_000., water_level: -0.3, mud_level: 0.3,
+            open: ZoneConfig::default(),
+            water: ZoneConfig { linear_damping: 4., friction: 0.1, stamina_cost: 2., regrow: 0.2 },
+            mud: ZoneConfig { linear_damping: 6., friction: 0.8, stamina_cost: 3., regrow: 2. },
+        }
+    }
+}
+
+impl TerrainConfig {
+    pub fn zone(&self, kind: TerrainKind) -> &ZoneConfig {
+        match kind {
+            TerrainKind::Open => &self.open,
+            TerrainKind::Water => &self.water,
+            TerrainKind::Mud => &self.mud,
+        }
+    }
+}
+
+/// Noise field the zones are cut from, seeded from the [`WorldSeed`].
+#[derive(Resource, Clone)]
+pub struct Terrain {
+    pub noise: Perlin,
+    /// `regrow` averaged over the regrowth range, so that zones move food around without changing the total
+    regrow_mean: f32,
+}
+
+impl Terrain {
+    pub fn kind(&self, c: &TerrainConfig, p: Vec2) -> TerrainKind {
+        if !c.enabled { return TerrainKind::Open }
+        let p = p / c.scale.max(1.);
+        let n = self.noise.get([p.x as f64, p.y as f64]) as f32;
+        if n < c.water_level { TerrainKind::Water }
+        else if n > c.mud_level { TerrainKind::Mud }
+        else { TerrainKind::Open }
+    }
+
+    /// Multiplier of the regrowth chance at `p`, 1 on average over the zones that actually occur.
+    pub fn regrow(&self, c: &TerrainConfig, p: Vec2) -> f32 {
+        if !c.enabled { return 1. }
+        if self.regrow_mean <= 0. { return 0. }
+        c.zone(self.kind(c, p)).regrow / self.regrow_mean
+    }
+
+    /// `regrow` of the zones within `±range`, sampled on a grid.
+    fn mean_regrow(&self, c: &TerrainConfig, range: f32) -> f32 {
+        let n = 64;
+        let sum = (0..n * n).map(|i| {
+            let q = (Vec2::new((i % n) as f32, (i / n) as f32) + 0.5) / n as f32 * 2. - 1.;
+            c.zone(self.kind(c, q * range)).regrow
+        } ).sum::<f32>();
+        sum / (n * n) as f32
+    }
+}
+
+fn mean_system(
+    config: Res<SimConfig>,
+    mut terrain: ResMut<Terrain>,
+){
+    let mean = terrain.mean_regrow(&config.terrain, config.food.regrow_range);
+    if terrain.regrow_mean != mean { terrain.regrow_mean = mean; }
+}
+
+/// Zone the sectarian currently stands in.
+#[derive(Component, Clone, Copy, Default)]
+pub struct Zone(pub TerrainKind);
+
+fn zone_system(
+    config: Res<SimConfig>,
+    terrain: Res<Terrain>,
+    mut sectarii: Query<(&Transform, &mut Zone, &mut LinearDamping, &mut Friction), With<Sectarian>>,
+){
+    let c = &config.terrain;
+    sectarii.par_iter_mut().for_each(|(transform, mut zone, mut damping, mut friction)| {
+        let kind = terrain.kind(c, transform.translation.truncate());
+        let z = c.zone(kind);
+        if zone.0 != kind { zone.0 = kind; }
+        if damping.0 != z.linear_damping { damping.0 = z.linear_damping; }
+        if friction.dynamic_coefficient != z.friction { *friction = Friction::new(z.friction); }
+    } );
+}
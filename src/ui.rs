@@ -2,11 +2,12 @@ use std::fs;
 
 use bevy::{input::common_conditions::input_just_pressed, prelude::*, render::camera::Viewport};
 use bevy_egui::{EguiContexts, EguiPlugin, egui};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
 use bevy_pancam::{PanCam, PanCamPlugin};
 use iyes_perf_ui::prelude::*;
 use rusty_neat::visu;
 
-use crate::{sectarii::{Brain, Neat, Sectarian, Species}, Hp, Satiety, Stamina};
+use sectarii::{body::Body, config::SimConfig, death::{DeathCause, DeathStats}, sectarii::{Brain, Neat, Sectarian, Species, SpeciesHues}, lineage::{ExportLineage, Lineage}, snapshot::{LoadSnapshot, SaveSnapshot}, stats::{Stats, StatsSample}, Hp, Satiety, Stamina};
 
 
 pub struct UiPlugin;
@@ -21,9 +22,10 @@ impl Plugin for UiPlugin {
             .add_plugins(PanCamPlugin)
             .add_plugins(EguiPlugin)
             .insert_resource(Graph::default())
+            .insert_resource(Phylogeny::default())
             //.add_systems(Startup, minimap_camera)
             .add_systems(Startup, main_camera)
-            .add_systems(Update, (save_load, ui_update).chain())
+            .add_systems(Update, (save_load, ui_update, plots_update, phylogeny_update).chain())
             .add_systems(Update, 
                 cursor_system.run_if(input_just_pressed(MouseButton::Right)))
         ;
@@ -34,8 +36,15 @@ fn save_load(
     mut neat: ResMut<Neat>,
     mut graph: ResMut<Graph>,
     mut contexts: EguiContexts,
+    config: Res<SimConfig>,
+    mut save: EventWriter<SaveSnapshot>,
+    mut load: EventWriter<LoadSnapshot>,
+    mut export: EventWriter<ExportLineage>,
     keys: Res<ButtonInput<KeyCode>>,
 ){
+    if keys.just_pressed(KeyCode::F5) { save.send(SaveSnapshot(config.snapshot.path.clone())); }
+    if keys.just_pressed(KeyCode::F9) { load.send(LoadSnapshot(config.snapshot.path.clone())); }
+    if keys.just_pressed(KeyCode::F6) { export.send(ExportLineage(config.lineage.path.clone())); }
     if keys.just_pressed(KeyCode::Digit1) {
         if let Some(a) = neat.0.agents.get(&graph.key) {
             a.save("assets/saved.toml");
@@ -59,8 +68,9 @@ fn ui_update(
     time: Res<Time>, 
     mut contexts: EguiContexts,
     neat: Res<Neat>,
+    deaths: Res<DeathStats>,
     mut graph: ResMut<Graph>,
-    sectarii: Query<(&Transform, &Brain, &Species, &Hp, &Satiety, &Stamina), (With<Sectarian>, Without<Halo>)>,
+    sectarii: Query<(&Transform, &Brain, &Species, &Hp, &Satiety, &Stamina, &Body), (With<Sectarian>, Without<Halo>)>,
     mut halo: Query<&mut Transform, (With<Halo>, Without<Sectarian>)>
 ) {
     let mut hp = 0.;
@@ -70,6 +80,7 @@ fn ui_update(
     let mut angle = 0.;
     let mut species = 0;
     let mut sectarian_key = 0;
+    let mut body = None;
     if graph.entity.is_some() {
         if let Ok(sectarian) = sectarii.get(graph.entity.unwrap()) {
             let mut halo_t = halo.get_single_mut().unwrap();
@@ -82,6 +93,7 @@ fn ui_update(
             stamina = sectarian.5.0;
             velocity = sectarian.1.linvel;
             angle = sectarian.1.angvel;
+            body = Some(sectarian.6.clone());
         }
     }
     if graph.key != graph.key_old {
@@ -97,6 +109,15 @@ fn ui_update(
         ui.separator();
         ui.label(format!("Key: {}", sectarian_key));
         ui.label(format!("Species: {}", species));
+        if let Some(d) = deaths.0.get(&species) {
+            let causes = d.causes.iter().map(|(c, n)| format!("{:?} {}", c, n) ).collect::<Vec<_>>().join(", ");
+            ui.label(format!("Species deaths: {}", causes));
+            ui.label(format!("Species kills: {}", d.kills.values().sum::<usize>()));
+        }
+        if let Some(b) = body {
+            ui.label(format!("Body: size {:>.2}, density {:>.2}, steal {:>.1}", b.size, b.density, b.steal_radius));
+            ui.label(format!("Sight: range {:>.0}, fov {:>.2} rad", b.sight_range, b.sight_fov));
+        }
         ui.separator();
         ui.add(egui::ProgressBar::new(velocity / 50.).fill(egui::Color32::from_rgb(48, 48, 8))
             .text(format!("Velocity linear:  {:>.1} p/s", velocity)));
@@ -113,6 +134,106 @@ fn ui_update(
     });
 }
 
+fn plots_update(
+    mut contexts: EguiContexts,
+    stats: Res<Stats>,
+) {
+    let series = |f: fn(&StatsSample) -> f64| -> PlotPoints {
+        stats.history.iter().map(|s| [s.time as f64, f(s)] ).collect()
+    };
+    let plot = |id: &str| Plot::new(id.to_string()).height(120.).legend(Legend::default()).allow_scroll(false);
+
+    egui::Window::new("Plots").default_open(false).show(contexts.ctx_mut(), |ui| {
+        plot("population").show(ui, |p| {
+            p.line(Line::new(series(|s| s.population as f64)).name("Population"));
+        });
+        plot("species").show(ui, |p| {
+            p.line(Line::new(series(|s| s.species as f64)).name("Species"));
+            p.line(Line::new(series(|s| s.species_threshold as f64)).name("Threshold"));
+        });
+        plot("foods").show(ui, |p| {
+            p.line(Line::new(series(|s| s.foods as f64)).name("Food"));
+        });
+        plot("deaths").show(ui, |p| {
+            DeathCause::ALL.into_iter().for_each(|c| {
+                let points: PlotPoints = stats.history.iter().map(|s| [s.time as f64, s.deaths_by_cause[c as usize] as f64] ).collect();
+                p.line(Line::new(points).name(format!("{:?}", c)));
+            } );
+        });
+        plot("fitness").show(ui, |p| {
+            p.line(Line::new(series(|s| s.fitness_food as f64)).name("Grazing"));
+            p.line(Line::new(series(|s| s.fitness_predation as f64)).name("Predation"));
+        });
+    });
+}
+
+#[derive(Resource, Default)]
+pub struct Phylogeny {
+    /// species whose members are circled on the map
+    selected: Option<usize>,
+    hide_extinct: bool,
+}
+
+// species as horizontal branches over time, from founding to extinction, children right below their parent
+fn phylogeny_update(
+    mut contexts: EguiContexts,
+    mut phylogeny: ResMut<Phylogeny>,
+    mut gizmos: Gizmos,
+    lineage: Res<Lineage>,
+    hues: Res<SpeciesHues>,
+    sectarii: Query<(&Transform, &Species), With<Sectarian>>,
+) {
+    let row = 14.;
+    let end = lineage.elapsed.max(1.);
+    let tree = lineage.species_tree().into_iter()
+        .filter(|(s, _)| !phylogeny.hide_extinct || lineage.species[s].extinct.is_none() )
+        .collect::<Vec<_>>();
+    let color = |s: &usize| {
+        if lineage.species[s].extinct.is_some() { return egui::Color32::DARK_GRAY }
+        let c = Color::hsv(hues.0.get(s).copied().unwrap_or(0.), 0.8, 0.9).to_srgba();
+        egui::Color32::from_rgb((c.red * 255.) as u8, (c.green * 255.) as u8, (c.blue * 255.) as u8)
+    };
+
+    egui::Window::new("Phylogeny").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.checkbox(&mut phylogeny.hide_extinct, "Hide extinct");
+        egui::ScrollArea::vertical().max_height(500.).show(ui, |ui| {
+            let width = ui.available_width().max(300.);
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(width, row * tree.len() as f32), egui::Sense::click());
+            let painter = ui.painter_at(rect);
+            let x = |t: f32| rect.left() + 4. + (t / end).clamp(0., 1.) * (width - 48.);
+            let y = |i: usize| rect.top() + row * (i as f32 + 0.5);
+            let rows = tree.iter().enumerate().map(|(i, (s, _))| (*s, i) ).collect::<std::collections::HashMap<_, _>>();
+
+            tree.iter().enumerate().for_each(|(i, (s, _))| {
+                let r = &lineage.species[s];
+                if phylogeny.selected == Some(*s) {
+                    painter.rect_filled(egui::Rect::from_x_y_ranges(rect.x_range(), (y(i) - row / 2.)..=(y(i) + row / 2.)), 0., egui::Color32::from_gray(40));
+                }
+                if let Some(p) = r.parent.and_then(|p| rows.get(&p) ) {
+                    painter.line_segment([egui::pos2(x(r.founded), y(*p)), egui::pos2(x(r.founded), y(i))], egui::Stroke::new(1., egui::Color32::GRAY));
+                }
+                let width = 1. + (r.population as f32).sqrt().min(7.);
+                let stop = x(r.extinct.unwrap_or(end));
+                painter.line_segment([egui::pos2(x(r.founded), y(i)), egui::pos2(stop, y(i))], egui::Stroke::new(width, color(s)));
+                painter.text(egui::pos2(stop + 4., y(i)), egui::Align2::LEFT_CENTER, format!("{} ({})", s, r.population),
+                    egui::FontId::monospace(9.), egui::Color32::LIGHT_GRAY);
+            } );
+
+            if let Some(pos) = response.clicked().then(|| response.interact_pointer_pos() ).flatten() {
+                let i = ((pos.y - rect.top()) / row) as usize;
+                let clicked = tree.get(i).map(|(s, _)| *s );
+                phylogeny.selected = if phylogeny.selected == clicked { None } else { clicked };
+            }
+        });
+    });
+
+    if let Some(selected) = phylogeny.selected {
+        sectarii.iter().filter(|(_, s)| s.0 == selected ).for_each(|(t, _)| {
+            gizmos.circle_2d(Isometry2d::from_translation(t.translation.truncate()), 20., Color::hsv(60., 0.9, 1.));
+        } );
+    }
+}
+
 #[derive(Resource)]
 pub struct Graph {
     pub key: usize,
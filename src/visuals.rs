@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, render::{render_asset::RenderAssetUsages, render_resource::{Extent3d, TextureDimension, TextureFormat}}};
+
+use sectarii::{body::Body, bullets::Bullet, config::SimConfig, environment::Environment, food::{Food, FoodKind}, map::Obstacle, sectarii::Sectarian, terrain::{Terrain, TerrainKind}, world::WorldShape};
+
+pub struct VisualsPlugin;
+
+impl Plugin for VisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(Handlers::default())
+            .add_systems(Startup, (setup_visuals, terrain_visuals))
+            .add_systems(Update, (sectarii_visuals, food_visuals, bullet_visuals))
+            .add_systems(Update, (world_visuals, obstacle_visuals, daylight_visuals))
+        ;
+    }
+}
+
+#[derive(Resource)]
+struct Handlers {
+    mesh_sectarii: Option<Handle<Mesh>>,
+    /// one material per degree of hue
+    material_sectarii: HashMap<u16, Handle<ColorMaterial>>,
+    mesh_food: Option<Handle<Mesh>>,
+    material_food: HashMap<FoodKind, Handle<ColorMaterial>>,
+    mesh_bullet: Option<Handle<Mesh>>,
+    material_bullet: Option<Handle<ColorMaterial>>,
+}
+impl Default for Handlers {
+    fn default() -> Self {
+        Self { 
+            mesh_sectarii: None, material_sectarii: HashMap::new(), 
+            mesh_food: None, material_food: HashMap::new(), 
+            mesh_bullet: None, material_bullet: None 
+        }
+    }
+}
+
+fn setup_visuals(
+    mut handlers: ResMut<Handlers>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<SimConfig>,
+){
+    handlers.mesh_sectarii = Some( meshes.add(Triangle2d::new(Vec2::Y * -8_f32, Vec2::X * 2_f32, Vec2::X * -2_f32)) );
+    handlers.mesh_food = Some( meshes.add( Circle::new(3.) ) );
+    FoodKind::ALL.into_iter().for_each(|k| {
+        let (h, s, v) = config.food.kind(k).color;
+        handlers.material_food.insert(k, materials.add(Color::hsv(h, s, v)));
+    } );
+    handlers.mesh_bullet = Some( meshes.add( Circle::new(1.5) ) );
+    handlers.material_bullet = Some( materials.add(Color::hsv(30., 1., 1.)) );
+}
+
+// the mesh follows the body size through the transform scale
+fn sectarii_visuals(
+    mut commands: Commands,
+    mut handlers: ResMut<Handlers>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    sectarii: Query<(Entity, &Body), Added<Sectarian>>,
+){
+    sectarii.iter().for_each(|(e, body)| {
+        let hue = body.hue.rem_euclid(360.) as u16;
+        let mesh = handlers.mesh_sectarii.clone().unwrap();
+        let material = handlers.material_sectarii.entry(hue)
+            .or_insert_with(|| materials.add(Color::hsv(hue as f32, 1_f32, 1_f32)) )
+            .clone();
+        commands.entity(e).insert((Mesh2d(mesh), MeshMaterial2d(material)));
+    } );
+}
+
+fn food_visuals(
+    mut commands: Commands,
+    handlers: Res<Handlers>,
+    foods: Query<(Entity, &FoodKind), Added<Food>>,
+){
+    foods.iter().for_each(|(e, kind)| {
+        commands.entity(e).insert((
+            Mesh2d( handlers.mesh_food.clone().unwrap() ),
+            MeshMaterial2d( handlers.material_food[kind].clone() ),
+        ));
+    } );
+}
+
+fn bullet_visuals(
+    mut commands: Commands,
+    handlers: Res<Handlers>,
+    bullets: Query<Entity, Added<Bullet>>,
+){
+    bullets.iter().for_each(|e| {
+        commands.entity(e).insert((
+            Mesh2d( handlers.mesh_bullet.clone().unwrap() ),
+            MeshMaterial2d( handlers.material_bullet.clone().unwrap() ),
+        ));
+    } );
+}
+
+fn obstacle_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    obstacles: Query<(Entity, &Obstacle), Added<Obstacle>>,
+){
+    if obstacles.is_empty() { return }
+    let material = materials.add(Color::hsv(30., 0.2, 0.35));
+    obstacles.iter().for_each(|(e, o)| {
+        let mesh = match o {
+            Obstacle::Circle { radius, .. } => meshes.add(Circle::new(*radius)),
+            Obstacle::Rect { half_size, .. } => meshes.add(Rectangle::from_size(*half_size * 2.)),
+            Obstacle::Segment { a, b, radius } => meshes.add(Capsule2d::new(*radius, a.distance(*b))),
+        };
+        commands.entity(e).insert((Mesh2d(mesh), MeshMaterial2d(material.clone())));
+    } );
+}
+
+// zones are baked once into a background image, later config changes aren't shown
+fn terrain_visuals(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    config: Res<SimConfig>,
+    terrain: Res<Terrain>,
+){
+    let c = &config.terrain;
+    if !c.enabled { return }
+    let half = match config.world.shape {
+        WorldShape::None => Vec2::splat(config.food.regrow_range),
+        WorldShape::Rectangle | WorldShape::Torus => Vec2::from(config.world.half_size),
+        WorldShape::Circle => Vec2::splat(config.world.radius),
+    };
+    let res = 512;
+    let data = (0..res).flat_map(|y| (0..res).map(move |x| (x, y)) ).flat_map(|(x, y)| {
+        // image rows go top to bottom
+        let p = (Vec2::new(x as f32 + 0.5, (res - y) as f32 - 0.5) / res as f32 * 2. - 1.) * half;
+        match terrain.kind(c, p) {
+            TerrainKind::Open => [0, 0, 0, 0],
+            TerrainKind::Water => [40, 70, 140, 255],
+            TerrainKind::Mud => [90, 70, 40, 255],
+        }
+    } ).collect::<Vec<u8>>();
+    let image = Image::new(
+        Extent3d { width: res, height: res, depth_or_array_layers: 1 }, TextureDimension::D2,
+        data, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::RENDER_WORLD,
+    );
+    commands.spawn((
+        Sprite { image: images.add(image), custom_size: Some(half * 2.), ..default() },
+        Transform::from_xyz(0., 0., -1.),
+    ));
+}
+
+fn daylight_visuals(
+    mut clear: ResMut<ClearColor>,
+    config: Res<SimConfig>,
+    environment: Res<Environment>,
+){
+    if !config.environment.enabled { return }
+    let daylight = environment.daylight(&config.environment);
+    clear.0 = Color::hsv(230., 0.4 * (1. - daylight), 0.04 + 0.14 * daylight);
+}
+
+fn world_visuals(
+    mut gizmos: Gizmos,
+    config: Res<SimConfig>,
+){
+    let c = &config.world;
+    let size = Vec2::from(c.half_size) * 2.;
+    match c.shape {
+        WorldShape::None => {}
+        WorldShape::Rectangle => gizmos.rect_2d(Isometry2d::IDENTITY, size, Color::hsv(0., 0., 0.6)),
+        WorldShape::Torus => gizmos.rect_2d(Isometry2d::IDENTITY, size, Color::hsv(200., 0.5, 0.6)),
+        WorldShape::Circle => { gizmos.circle_2d(Isometry2d::IDENTITY, c.radius, Color::hsv(0., 0., 0.6)).resolution(256); }
+    }
+}
+
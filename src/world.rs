@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use avian2d::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{bullets::Bullet, config::SimConfig, sectarii::Sectarian, GameLayer, SimSet};
+
+pub struct WorldPlugin;
+
+impl Plugin for WorldPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Startup, setup_walls)
+            .add_systems(FixedUpdate, wrap_system.before(SimSet::Senses))
+        ;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WorldShape {
+    /// endless plane
+    None,
+    Rectangle,
+    Circle,
+    /// rectangle whose opposite edges are joined
+    Torus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct WorldConfig {
+    pub shape: WorldShape,
+    /// half extents of a rectangle or torus
+    pub half_size: (f32, f32),
+    /// radius of a circle
+    pub radius: f32,
+    pub wall_thickness: f32,
+}
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self { shape: WorldShape::Rectangle, half_size: (8_000., 8_000.), radius: 8_000., wall_thickness: 64. }
+    }
+}
+
+impl WorldConfig {
+    pub fn contains(&self, p: Vec2) -> bool {
+        match self.shape {
+            WorldShape::None => true,
+            WorldShape::Rectangle | WorldShape::Torus => p.x.abs() <= self.half_size.0 && p.y.abs() <= self.half_size.1,
+            WorldShape::Circle => p.length() <= self.radius,
+        }
+    }
+
+    /// Closest point inside the world.
+    pub fn clamp(&self, p: Vec2) -> Vec2 {
+        match self.shape {
+            WorldShape::None => p,
+            WorldShape::Rectangle | WorldShape::Torus => p.clamp(-Vec2::from(self.half_size), Vec2::from(self.half_size)),
+            WorldShape::Circle => p.clamp_length_max(self.radius),
+        }
+    }
+
+    /// Uniform point in the `±range` square that lies inside the world.
+    pub fn sample(&self, rng: &mut impl Rng, range: f32) -> Vec2 {
+        let mut p = Vec2::ZERO;
+        for _ in 0..16 {
+            p = Vec2::new(rng.random_range(-range..=range), rng.random_range(-range..=range));
+            if self.contains(p) { return p }
+        }
+        self.clamp(p)
+    }
+}
+
+#[derive(Component, Clone)]
+pub struct Wall;
+
+fn setup_walls(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+){
+    let c = &config.world;
+    let t = c.wall_thickness;
+    let layers = CollisionLayers::new([GameLayer::Default, GameLayer::Walls], [GameLayer::Sectarii, GameLayer::Foods, GameLayer::Bullets]);
+
+    match c.shape {
+        WorldShape::None | WorldShape::Torus => {}
+        WorldShape::Rectangle => {
+            let (w, h) = c.half_size;
+            [
+                (Vec2::new(0., h + t/2.), Vec2::new(2. * (w + t), t)),
+                (Vec2::new(0., -h - t/2.), Vec2::new(2. * (w + t), t)),
+                (Vec2::new(w + t/2., 0.), Vec2::new(t, 2. * (h + t))),
+                (Vec2::new(-w - t/2., 0.), Vec2::new(t, 2. * (h + t))),
+            ].into_iter().for_each(|(p, size)| {
+                commands.spawn((
+                    Wall, RigidBody::Static, Collider::rectangle(size.x, size.y), layers,
+                    Transform::from_translation(p.extend(0.)),
+                ));
+            } );
+        }
+        WorldShape::Circle => {
+            // ring of blocks `t` thick, long enough to close the gaps on the outer edge
+            let segments = 256;
+            let step = std::f32::consts::TAU / segments as f32;
+            let length = 2. * (c.radius + t) * (step / 2.).tan();
+            let blocks = (0..segments).map(|i| {
+                let angle = i as f32 * step;
+                (Position::new(Vec2::from_angle(angle) * (c.radius + t/2.)), Rotation::radians(angle), Collider::rectangle(t, length))
+            } ).collect::<Vec<_>>();
+            commands.spawn((
+                Wall, RigidBody::Static, Collider::compound(blocks), layers,
+                Transform::default(),
+            ));
+        }
+    }
+}
+
+fn wrap_system(
+    config: Res<SimConfig>,
+    mut entities: Query<(&mut Transform, &mut Position), Or<(With<Sectarian>, With<Bullet>)>>,
+){
+    let c = &config.world;
+    if c.shape != WorldShape::Torus { return }
+    let half = Vec2::from(c.half_size);
+
+    entities.par_iter_mut().for_each(|(mut transform, mut position)| {
+        let p = position.0;
+        let wrapped = (p + half).rem_euclid(half * 2.) - half;
+        if wrapped != p {
+            position.0 = wrapped;
+            transform.translation = wrapped.extend(transform.translation.z);
+        }
+    } );
+}